@@ -0,0 +1,210 @@
+//! A `Marshall` over language-neutral values.
+//!
+//! This lets Rust code call pluggable methods by name, without a VM.
+
+use {Marshall, MarshallError, Pluggable, PluggableValue, Value, Variant};
+
+use std::convert::TryFrom;
+
+/// Marshalls Rust values to and from `plugger_core::Value`.
+///
+/// There is no VM to raise errors in, so this isn't a `Raise`. Methods called
+/// through it return their errors instead.
+pub struct DynamicMarshall;
+
+impl Marshall for DynamicMarshall {
+    type Value = Value;
+
+    fn nil() -> Value { Value::Nil }
+    fn is_nil(value: &Value) -> bool { value.is_nil() }
+
+    fn is_truthy(value: &Value) -> bool {
+        !matches!(*value, Value::Nil | Value::Bool(false))
+    }
+
+    fn to_bool(value: Value) -> Result<bool, MarshallError> {
+        value.as_bool().ok_or_else(|| type_mismatch("Bool", &value))
+    }
+
+    fn to_u8(value: Value) -> Result<u8, MarshallError> { to_integer("u8", value) }
+    fn to_u16(value: Value) -> Result<u16, MarshallError> { to_integer("u16", value) }
+    fn to_u32(value: Value) -> Result<u32, MarshallError> { to_integer("u32", value) }
+    fn to_u64(value: Value) -> Result<u64, MarshallError> { to_integer("u64", value) }
+    fn to_i8(value: Value) -> Result<i8, MarshallError> { to_integer("i8", value) }
+    fn to_i16(value: Value) -> Result<i16, MarshallError> { to_integer("i16", value) }
+    fn to_i32(value: Value) -> Result<i32, MarshallError> { to_integer("i32", value) }
+    fn to_i64(value: Value) -> Result<i64, MarshallError> { to_integer("i64", value) }
+    fn to_u128(value: Value) -> Result<u128, MarshallError> { to_integer("u128", value) }
+    fn to_i128(value: Value) -> Result<i128, MarshallError> { to_integer("i128", value) }
+    fn to_usize(value: Value) -> Result<usize, MarshallError> { to_integer("usize", value) }
+    fn to_isize(value: Value) -> Result<isize, MarshallError> { to_integer("isize", value) }
+
+    fn to_f32(value: Value) -> Result<f32, MarshallError> {
+        Self::to_f64(value).map(|f| f as f32)
+    }
+
+    fn to_f64(value: Value) -> Result<f64, MarshallError> {
+        match value {
+            Value::Float(f) => Ok(f),
+            Value::Int(i) => Ok(i as f64),
+            Value::UInt(u) => Ok(u as f64),
+            value => Err(type_mismatch("Float", &value)),
+        }
+    }
+
+    fn to_char(value: Value) -> Result<char, MarshallError> {
+        let s = Self::to_string(value)?;
+        let mut chars = s.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(MarshallError::TypeMismatch {
+                expected: "a single character".to_owned(),
+                got: format!("{} characters", s.chars().count()),
+            }),
+        }
+    }
+
+    fn to_string(value: Value) -> Result<String, MarshallError> {
+        match value {
+            Value::String(s) => Ok(s),
+            value => Err(type_mismatch("String", &value)),
+        }
+    }
+
+    fn from_bool(value: bool) -> Value { Value::Bool(value) }
+    fn from_u8(value: u8) -> Value { Value::Int(value as i128) }
+    fn from_u16(value: u16) -> Value { Value::Int(value as i128) }
+    fn from_u32(value: u32) -> Value { Value::Int(value as i128) }
+    fn from_u64(value: u64) -> Value { Value::Int(value as i128) }
+    fn from_i8(value: i8) -> Value { Value::Int(value as i128) }
+    fn from_i16(value: i16) -> Value { Value::Int(value as i128) }
+    fn from_i32(value: i32) -> Value { Value::Int(value as i128) }
+    fn from_i64(value: i64) -> Value { Value::Int(value as i128) }
+    fn from_i128(value: i128) -> Value { Value::Int(value) }
+    fn from_usize(value: usize) -> Value { Value::Int(value as i128) }
+    fn from_isize(value: isize) -> Value { Value::Int(value as i128) }
+    fn from_f32(value: f32) -> Value { Value::Float(value as f64) }
+    fn from_f64(value: f64) -> Value { Value::Float(value) }
+    fn from_char(value: char) -> Value { Value::String(value.to_string()) }
+    fn from_string(value: String) -> Value { Value::String(value) }
+
+    /// Values above `i128::MAX` don't fit in a `Value::Int`, and become a
+    /// `Value::UInt`.
+    fn from_u128(value: u128) -> Value {
+        i128::try_from(value).map(Value::Int).unwrap_or(Value::UInt(value))
+    }
+
+    fn to_bytes(value: Value) -> Result<Vec<u8>, MarshallError> {
+        match value {
+            Value::Bytes(bytes) => Ok(bytes),
+            value => Err(type_mismatch("Bytes", &value)),
+        }
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Value { Value::Bytes(bytes.to_owned()) }
+
+    fn to_array(value: Value) -> Result<Vec<Value>, MarshallError> {
+        match value {
+            Value::Array(values) => Ok(values),
+            value => Err(type_mismatch("Array", &value)),
+        }
+    }
+
+    fn from_array(values: Vec<Value>) -> Value { Value::Array(values) }
+
+    fn to_map(value: Value) -> Result<Vec<(Value, Value)>, MarshallError> {
+        match value {
+            Value::Map(pairs) => Ok(pairs),
+            value => Err(type_mismatch("Map", &value)),
+        }
+    }
+
+    fn from_map(pairs: Vec<(Value, Value)>) -> Value { Value::Map(pairs) }
+
+    fn to_symbol(value: Value) -> Result<String, MarshallError> {
+        match value {
+            Value::Symbol(s) | Value::String(s) => Ok(s),
+            value => Err(type_mismatch("Symbol", &value)),
+        }
+    }
+
+    fn from_symbol(name: String) -> Value { Value::Symbol(name) }
+
+    /// Variants are either a plain symbol, or a map with `variant` and
+    /// `fields` entries.
+    fn to_variant<T>(value: Value) -> Result<Variant<Value>, MarshallError>
+        where T: PluggableValue {
+        if let Value::Symbol(name) = value {
+            return Ok(Variant { name, fields: Vec::new() });
+        }
+
+        let mut pairs = match value {
+            Value::Map(pairs) => pairs,
+            value => return Err(type_mismatch(T::type_name(), &value)),
+        };
+        let name = Self::to_symbol(take_entry(&mut pairs, "variant")?)?;
+        let fields = Self::to_map(take_entry(&mut pairs, "fields")?)?.into_iter().map(|(name, value)| {
+            Ok((Self::to_symbol(name)?, value))
+        }).collect::<Result<_, _>>()?;
+
+        Ok(Variant { name, fields })
+    }
+
+    fn from_variant<T>(variant: Variant<Value>) -> Value
+        where T: PluggableValue {
+        let fields = variant.fields.into_iter().map(|(name, value)| (Value::Symbol(name), value)).collect();
+
+        Value::Map(vec![
+            (Value::Symbol("variant".to_owned()), Value::Symbol(variant.name)),
+            (Value::Symbol("fields".to_owned()), Value::Map(fields)),
+        ])
+    }
+
+    fn to_dynamic(value: Value) -> Result<Value, MarshallError> { Ok(value) }
+    fn from_dynamic(value: Value) -> Result<Value, MarshallError> { Ok(value) }
+
+    fn object_pointer_from_value<T>(value: Value) -> Result<*mut T, MarshallError>
+        where T: Pluggable {
+        match value {
+            Value::Object(handle) => handle.downcast::<T>().ok_or_else(|| MarshallError::TypeMismatch {
+                expected: T::type_name().to_owned(),
+                got: handle.type_name().to_owned(),
+            }),
+            value => Err(type_mismatch(T::type_name(), &value)),
+        }
+    }
+}
+
+/// Removes an entry, keyed by symbol, from a map.
+fn take_entry(pairs: &mut Vec<(Value, Value)>, name: &str) -> Result<Value, MarshallError> {
+    let key = Value::Symbol(name.to_owned());
+
+    match pairs.iter().position(|pair| pair.0 == key) {
+        Some(index) => Ok(pairs.remove(index).1),
+        None => Err(MarshallError::MissingField(name.to_owned())),
+    }
+}
+
+/// Converts an `Int` or `UInt` into a Rust integer, checking that it fits.
+fn to_integer<T>(ty: &str, value: Value) -> Result<T, MarshallError>
+    where T: TryFrom<i128> + TryFrom<u128> {
+    match value {
+        Value::Int(i) => T::try_from(i).map_err(|_| out_of_range(ty, i.to_string())),
+        Value::UInt(u) => T::try_from(u).map_err(|_| out_of_range(ty, u.to_string())),
+        value => Err(type_mismatch("Int", &value)),
+    }
+}
+
+/// Creates an error for a number that doesn't fit in a Rust type.
+fn out_of_range(ty: &str, value: String) -> MarshallError {
+    MarshallError::OutOfRange { ty: ty.to_owned(), value }
+}
+
+/// Creates an error for a value that is not of the expected kind.
+fn type_mismatch(expected: &str, value: &Value) -> MarshallError {
+    MarshallError::TypeMismatch {
+        expected: expected.to_owned(),
+        got: value.kind_name().to_owned(),
+    }
+}
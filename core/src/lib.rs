@@ -1,11 +1,48 @@
-use std::mem;
+#[cfg(feature = "serde")]
+extern crate serde;
 
-pub type TypeName = &'static str;
+#[cfg(feature = "serde")]
+pub mod serialization;
+
+pub use self::value::{ObjectHandle, Value};
+pub use self::dynamic::DynamicMarshall;
+pub use self::types::TypeDesc;
+
+pub mod schema;
+mod value;
+mod dynamic;
+mod types;
+
+use std::any::{Any, TypeId};
+use std::sync::Mutex;
+use std::{error, fmt};
+
+/// Calls a method with language-neutral arguments.
+///
+/// The receiver is ignored by static methods.
+pub type Invoker = fn(receiver: *mut (), args: Vec<Value>) -> Result<Value, MarshallError>;
 
 #[derive(Clone)]
 pub struct Parameter {
     pub name: String,
-    pub ty: TypeName,
+    pub ty: TypeDesc,
+}
+
+/// The options given to a method with `#[pluggable(...)]`, which change how
+/// its values are marshalled.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MethodOptions
+{
+    /// Whether `String` keys of maps are mapped to symbols.
+    pub symbol_keys: bool,
+    /// Whether symbols are accepted for `String`, `&str` and `Cow<str>`
+    /// parameters.
+    pub coerce_symbols: bool,
+    /// Whether `bool` parameters must be given exactly `true` or `false`.
+    pub strict_bool: bool,
+    /// Whether types other than the built-in ones are marshalled through
+    /// `serde`.
+    pub serde: bool,
 }
 
 #[derive(Clone)]
@@ -17,16 +54,22 @@ pub struct Method
     // NOTE: This is a vec and not a hash map because it's easier to construct
     // a Vec inside a syntax extension.
     pub lang_marshalls: Vec<(&'static str, *mut fn())>,
+    /// Calls the method through `DynamicMarshall`.
+    pub invoker: Invoker,
 
     /// The name of the method.
     pub name: &'static str,
     /// The parameter list.
     pub parameters: Vec<Parameter>,
     /// The return type (if any).
-    pub ret: Option<TypeName>,
+    pub ret: Option<TypeDesc>,
 
     /// Whether the method has a receiver.
     pub is_static: bool,
+    /// The documentation, from the method's doc comments.
+    pub docs: &'static str,
+    /// How the parameters and return value are marshalled.
+    pub options: MethodOptions,
 }
 
 #[derive(Clone)]
@@ -35,46 +78,150 @@ pub struct Field
     /// The number of bytes from the start of the structure.
     pub field_offset: usize,
 
-    pub ty: TypeName,
+    pub ty: TypeDesc,
     pub name: &'static str,
+    /// The documentation, from the field's doc comments.
+    pub docs: &'static str,
+}
+
+/// A constant, such as a `const` or `static` item, or an associated `const`.
+#[derive(Clone)]
+pub struct Constant
+{
+    pub name: &'static str,
+    pub ty: TypeDesc,
+    /// The documentation, from the constant's doc comments.
+    pub docs: &'static str,
+    /// A static method without parameters which returns the value.
+    pub getter: Method,
 }
 
 #[derive(Clone)]
 pub struct Class
 {
+    /// The identity of the Rust type.
+    pub type_id: TypeId,
     pub name: String,
+    /// The fully qualified path of the Rust type.
+    pub path: String,
+    /// The namespace given by `#[pluggable(namespace = "...")]`, if any.
+    ///
+    /// Otherwise languages derive the namespace from `path`.
+    pub namespace: Option<&'static str>,
+    /// The documentation, from the type's doc comments.
+    pub docs: &'static str,
     pub fields: Vec<Field>,
     pub methods: Vec<Method>,
+    /// The associated constants.
+    pub constants: Vec<Constant>,
+}
+
+/// A group of free functions, such as the public functions of a Rust module.
+///
+/// Created by placing `#[pluggable]` on a `mod` or on a single `fn`.
+#[derive(Clone)]
+pub struct Module
+{
+    /// The name of the Rust module.
+    pub name: String,
+    /// The fully qualified path of the Rust module.
+    pub path: String,
+    /// The namespace given by `#[pluggable(namespace = "...")]`, if any.
+    ///
+    /// Otherwise languages derive the namespace from `path`.
+    pub namespace: Option<&'static str>,
+    /// The documentation, from the module's doc comments.
+    pub docs: &'static str,
+    /// The functions. These are all static.
+    pub functions: Vec<Method>,
+    pub constants: Vec<Constant>,
 }
 
 pub trait PluggableFields
 {
-    fn pluggable_fields(&self) -> Vec<Field>;
+    fn pluggable_fields() -> Vec<Field>;
 }
 
 pub trait PluggableMethods
 {
-    fn pluggable_methods(&self) -> Vec<Method>;
+    fn pluggable_methods() -> Vec<Method>;
+
+    /// The associated constants of the pluggable `impl`.
+    fn pluggable_constants() -> Vec<Constant> { Vec::new() }
+}
+
+/// An error that occurred while marshalling a value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MarshallError
+{
+    /// A value was not of the expected type.
+    TypeMismatch { expected: String, got: String },
+    /// A number does not fit in the expected type.
+    OutOfRange { ty: String, value: String },
+    /// A string was not valid in its encoding, or could not be converted to UTF-8.
+    InvalidEncoding { encoding: String },
+    /// A collection did not have the expected number of elements.
+    LengthMismatch { expected: usize, got: usize },
+    /// An element of a collection could not be marshalled.
+    Element { index: usize, error: Box<MarshallError> },
+    /// A value did not name a variant of an enum.
+    UnknownVariant { ty: String, variant: String },
+    /// An enum variant was missing one of its fields.
+    MissingField(String),
+    /// The Rust function returned an error.
+    Returned(String),
+    /// A value could not be serialized or deserialized by `serde`.
+    Serialization(String),
+}
+
+/// A variant of a data-carrying enum, with its fields marshalled.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variant<V>
+{
+    /// The name of the variant, in `snake_case`.
+    pub name: String,
+    /// The fields of the variant.
+    ///
+    /// Tuple variants have fields named `field0`, `field1`, etc.
+    pub fields: Vec<(String, V)>,
 }
 
+/// The key-value pairs of a map, in the order the language gave them.
+pub type Pairs<V> = Vec<(V, V)>;
+
 /// An object that can marshall Rust values to an arbitrary value.
 pub trait Marshall
 {
     /// The value type used by the language.
-    type Value;
-
-    fn to_bool(value: Self::Value) -> bool;
-    fn to_u8(value: Self::Value) -> u8;
-    fn to_u16(value: Self::Value) -> u16;
-    fn to_u32(value: Self::Value) -> u32;
-    fn to_u64(value: Self::Value) -> u64;
-    fn to_i8(value: Self::Value) -> i8;
-    fn to_i16(value: Self::Value) -> i16;
-    fn to_i32(value: Self::Value) -> i32;
-    fn to_i64(value: Self::Value) -> i64;
-    fn to_f32(value: Self::Value) -> f32;
-    fn to_f64(value: Self::Value) -> f64;
-    fn to_string(value: Self::Value) -> String;
+    type Value: Clone;
+
+    /// Gets the language's equivalent of `nil`.
+    fn nil() -> Self::Value;
+    /// Checks if a value is the language's equivalent of `nil`.
+    fn is_nil(value: &Self::Value) -> bool;
+
+    /// Checks if a value is considered true by the language.
+    fn is_truthy(value: &Self::Value) -> bool;
+
+    /// Gets a boolean, failing if the value is not exactly `true` or `false`.
+    fn to_bool(value: Self::Value) -> Result<bool, MarshallError>;
+    fn to_u8(value: Self::Value) -> Result<u8, MarshallError>;
+    fn to_u16(value: Self::Value) -> Result<u16, MarshallError>;
+    fn to_u32(value: Self::Value) -> Result<u32, MarshallError>;
+    fn to_u64(value: Self::Value) -> Result<u64, MarshallError>;
+    fn to_i8(value: Self::Value) -> Result<i8, MarshallError>;
+    fn to_i16(value: Self::Value) -> Result<i16, MarshallError>;
+    fn to_i32(value: Self::Value) -> Result<i32, MarshallError>;
+    fn to_i64(value: Self::Value) -> Result<i64, MarshallError>;
+    fn to_u128(value: Self::Value) -> Result<u128, MarshallError>;
+    fn to_i128(value: Self::Value) -> Result<i128, MarshallError>;
+    fn to_usize(value: Self::Value) -> Result<usize, MarshallError>;
+    fn to_isize(value: Self::Value) -> Result<isize, MarshallError>;
+    fn to_f32(value: Self::Value) -> Result<f32, MarshallError>;
+    fn to_f64(value: Self::Value) -> Result<f64, MarshallError>;
+    fn to_char(value: Self::Value) -> Result<char, MarshallError>;
+    /// Gets a string, converting it to UTF-8 if necessary.
+    fn to_string(value: Self::Value) -> Result<String, MarshallError>;
 
     fn from_bool(value: bool) -> Self::Value;
     fn from_u8(value: u8) -> Self::Value;
@@ -85,20 +232,122 @@ pub trait Marshall
     fn from_i16(value: i16) -> Self::Value;
     fn from_i32(value: i32) -> Self::Value;
     fn from_i64(value: i64) -> Self::Value;
+    fn from_u128(value: u128) -> Self::Value;
+    fn from_i128(value: i128) -> Self::Value;
+    fn from_usize(value: usize) -> Self::Value;
+    fn from_isize(value: isize) -> Self::Value;
     fn from_f32(value: f32) -> Self::Value;
     fn from_f64(value: f64) -> Self::Value;
+    fn from_char(value: char) -> Self::Value;
     fn from_string(value: String) -> Self::Value;
 
-    fn reference_from_value<'a, T>(value: Self::Value) -> &'a T
+    fn from_str(value: &str) -> Self::Value {
+        Self::from_string(value.to_owned())
+    }
+
+    /// Gets the elements of an array.
+    fn to_array(value: Self::Value) -> Result<Vec<Self::Value>, MarshallError>;
+    /// Creates an array from a list of elements.
+    fn from_array(values: Vec<Self::Value>) -> Self::Value;
+
+    /// Gets the contents of a binary string.
+    ///
+    /// Languages without a binary string type can use the default, which
+    /// expects an array of integers.
+    fn to_bytes(value: Self::Value) -> Result<Vec<u8>, MarshallError> {
+        marshall_elements(Self::to_array(value)?, Self::to_u8)
+    }
+
+    /// Creates a binary string.
+    ///
+    /// Languages without a binary string type can use the default, which
+    /// creates an array of integers.
+    fn from_bytes(bytes: &[u8]) -> Self::Value {
+        Self::from_array(bytes.iter().map(|&byte| Self::from_u8(byte)).collect())
+    }
+
+    /// Gets the key-value pairs of a map.
+    fn to_map(value: Self::Value) -> Result<Pairs<Self::Value>, MarshallError>;
+    /// Creates a map from a list of key-value pairs.
+    fn from_map(pairs: Pairs<Self::Value>) -> Self::Value;
+
+    /// Gets the name of a symbol.
+    ///
+    /// Languages without symbols should accept strings here.
+    fn to_symbol(value: Self::Value) -> Result<String, MarshallError>;
+    /// Creates a symbol.
+    ///
+    /// Languages without symbols should create a string here.
+    fn from_symbol(name: String) -> Self::Value;
+
+    /// Gets the variant of a data-carrying enum.
+    fn to_variant<T>(value: Self::Value) -> Result<Variant<Self::Value>, MarshallError>
+        where T: PluggableValue;
+    /// Creates a value representing a variant of a data-carrying enum.
+    fn from_variant<T>(variant: Variant<Self::Value>) -> Self::Value
+        where T: PluggableValue;
+
+    /// Converts a value into a language-neutral `Value`.
+    fn to_dynamic(value: Self::Value) -> Result<Value, MarshallError>;
+    /// Converts a language-neutral `Value` into a value.
+    fn from_dynamic(value: Value) -> Result<Self::Value, MarshallError>;
+
+    fn reference_from_value<'a, T>(value: Self::Value) -> Result<&'a T, MarshallError>
         where T: Pluggable {
-        let ptr: *mut T = Self::object_pointer_from_value(value);
-        unsafe { mem::transmute(ptr) }
+        let ptr: *mut T = Self::object_pointer_from_value(value)?;
+        Ok(unsafe { &*ptr })
     }
 
-    fn object_pointer_from_value<T>(value: Self::Value) -> *mut T
+    fn object_pointer_from_value<T>(value: Self::Value) -> Result<*mut T, MarshallError>
         where T: Pluggable;
 }
 
+/// A `Marshall` for a language that calls into Rust from its own VM.
+///
+/// Errors can't be returned to the VM, so they are raised in it instead.
+pub trait Raise : Marshall
+{
+    /// Raises an error inside the language.
+    ///
+    /// This is called when marshalling fails, and should unwind back into the
+    /// VM that called the Rust function.
+    fn raise(error: MarshallError) -> !;
+}
+
+/// A type that is marshalled by value, rather than being referenced.
+///
+/// Can be automatically derived by placing `#[pluggable]` on an enum.
+pub trait PluggableValue : Sized
+{
+    /// The name of the type.
+    fn type_name() -> &'static str;
+    /// The fully qualified path of the type.
+    fn type_path() -> &'static str;
+    /// The namespace given by `#[pluggable(namespace = "...")]`, if any.
+    fn type_namespace() -> Option<&'static str> { None }
+
+    fn to_value<M>(self) -> Result<M::Value, MarshallError> where M: Marshall;
+    fn from_value<M>(value: M::Value) -> Result<Self, MarshallError> where M: Marshall;
+}
+
+/// Marshalls an element of a collection.
+///
+/// If the element fails to marshall, the error will include its index.
+pub fn marshall_element<V, T, F>(index: usize, value: V, f: F) -> Result<T, MarshallError>
+    where F: FnOnce(V) -> Result<T, MarshallError> {
+    f(value).map_err(|error| MarshallError::Element { index, error: Box::new(error) })
+}
+
+/// Marshalls every element of a collection.
+///
+/// If an element fails to marshall, the error will include its index.
+pub fn marshall_elements<I, T, F>(values: I, mut f: F) -> Result<Vec<T>, MarshallError>
+    where I: IntoIterator, F: FnMut(I::Item) -> Result<T, MarshallError> {
+    values.into_iter().enumerate().map(|(index, value)| {
+        marshall_element(index, value, &mut f)
+    }).collect()
+}
+
 /// An object that can be plugged into a scripting language.
 ///
 /// Can be automatically derived by placing `#[pluggable]` on a struct.
@@ -118,26 +367,258 @@ pub trait Marshall
 ///
 /// Because of this, it is necessary to mark both the `struct` and the `impl`
 /// with the `pluggable` attribute. It's not the prettiest, but it does work.
-pub trait Pluggable : PluggableFields + PluggableMethods
+pub trait Pluggable : PluggableFields + PluggableMethods + Any
 {
-    fn name(&self) -> &'static str;
+    /// The name of the type.
+    fn type_name() -> &'static str;
+    /// The fully qualified path of the type.
+    fn type_path() -> &'static str;
+    /// The documentation of the type.
+    fn type_docs() -> &'static str;
+    /// The namespace given by `#[pluggable(namespace = "...")]`, if any.
+    fn type_namespace() -> Option<&'static str> { None }
 
-    fn fields(&self) -> Vec<Field> { PluggableFields::pluggable_fields(self) }
-    fn methods(&self) -> Vec<Method> { PluggableMethods::pluggable_methods(self) }
+    fn fields() -> Vec<Field> { Self::pluggable_fields() }
+    fn methods() -> Vec<Method> { Self::pluggable_methods() }
+    fn constants() -> Vec<Constant> { Self::pluggable_constants() }
 
-    fn class(&self) -> Class {
+    /// Describes the type.
+    ///
+    /// This doesn't need an instance, so a class can be registered with a
+    /// language before any objects exist.
+    fn class() -> Class {
         Class {
-            name: self.name().to_owned(),
-            fields: self.fields(),
-            methods: self.methods(),
+            type_id: TypeId::of::<Self>(),
+            name: Self::type_name().to_owned(),
+            path: Self::type_path().to_owned(),
+            namespace: Self::type_namespace(),
+            docs: Self::type_docs(),
+            fields: Self::fields(),
+            methods: Self::methods(),
+            constants: Self::constants(),
         }
     }
 }
 
+/// A class description that can be kept in a static.
+struct RegisteredClass(Class);
+
+// The pointers in a description only ever point at functions.
+unsafe impl Send for RegisteredClass { }
+
+/// The classes registered for tooling, shared by every thread.
+static REGISTERED_CLASSES: Mutex<Vec<RegisteredClass>> = Mutex::new(Vec::new());
+
+/// Registers a class so that tooling, such as the schema exporter, can see it.
+///
+/// Registering a class with the same path again replaces it.
+pub fn register_class(class: Class) {
+    let mut classes = REGISTERED_CLASSES.lock().unwrap();
+
+    classes.retain(|existing| existing.0.path != class.path);
+    classes.push(RegisteredClass(class));
+}
+
+/// Gets every registered class, whichever thread registered it.
+pub fn registered_classes() -> Vec<Class> {
+    REGISTERED_CLASSES.lock().unwrap().iter().map(|class| class.0.clone()).collect()
+}
+
+/// An error from calling a method through `Class::invoke`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InvokeError
+{
+    /// The class has no method with the given name.
+    UnknownMethod(String),
+    /// The receiver is not an instance of the class.
+    WrongReceiver { expected: String, got: &'static str },
+    /// A static method was called with a receiver, or vice versa.
+    WrongStaticness { method: String, is_static: bool },
+    /// The method was given the wrong number of arguments.
+    ArgumentCount { expected: usize, got: usize },
+    /// An argument or the return value could not be marshalled.
+    Marshall(MarshallError),
+}
+
+impl Class {
+    /// Gets a method by name.
+    pub fn method(&self, name: &str) -> Option<&Method> {
+        self.methods.iter().find(|method| method.name == name)
+    }
+
+    /// Gets an associated constant by name.
+    pub fn constant(&self, name: &str) -> Option<&Constant> {
+        self.constants.iter().find(|constant| constant.name == name)
+    }
+
+    /// Calls a method on an object by name.
+    pub fn invoke<T>(&self, object: &mut T, method: &str, args: &[Value]) -> Result<Value, InvokeError>
+        where T: Pluggable {
+        if TypeId::of::<T>() != self.type_id {
+            return Err(InvokeError::WrongReceiver { expected: self.name.clone(), got: T::type_name() });
+        }
+
+        self.invoke_raw(object as *mut T as *mut (), false, method, args)
+    }
+
+    /// Calls a static method by name.
+    pub fn invoke_static(&self, method: &str, args: &[Value]) -> Result<Value, InvokeError> {
+        self.invoke_raw(::std::ptr::null_mut(), true, method, args)
+    }
+
+    fn invoke_raw(&self, receiver: *mut (), is_static: bool, name: &str, args: &[Value])
+        -> Result<Value, InvokeError> {
+        let method = self.method(name).ok_or_else(|| InvokeError::UnknownMethod(name.to_owned()))?;
+
+        if method.is_static != is_static {
+            return Err(InvokeError::WrongStaticness { method: name.to_owned(), is_static: method.is_static });
+        }
+        method.invoke(receiver, args)
+    }
+}
+
+impl Module {
+    /// Gets a function by name.
+    pub fn function(&self, name: &str) -> Option<&Method> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+
+    /// Gets a constant by name.
+    pub fn constant(&self, name: &str) -> Option<&Constant> {
+        self.constants.iter().find(|constant| constant.name == name)
+    }
+
+    /// Calls a function by name.
+    pub fn invoke(&self, name: &str, args: &[Value]) -> Result<Value, InvokeError> {
+        let function = self.function(name).ok_or_else(|| InvokeError::UnknownMethod(name.to_owned()))?;
+        function.invoke(::std::ptr::null_mut(), args)
+    }
+}
+
+impl Constant {
+    /// Gets the marshall for a language.
+    ///
+    /// This is a function without parameters which returns the value.
+    pub fn marshall(&self, lang_name: &str) -> *mut fn() {
+        self.getter.marshall(lang_name)
+    }
+
+    /// Gets the value as a language-neutral value.
+    pub fn value(&self) -> Result<Value, MarshallError> {
+        (self.getter.invoker)(::std::ptr::null_mut(), Vec::new())
+    }
+}
+
 impl Method {
     /// Gets the marshall for a language.
     pub fn marshall(&self, lang_name: &str) -> *mut fn() {
         self.lang_marshalls.iter().find(|m| m.0 == lang_name).unwrap().1
     }
+
+    /// Calls the method through its invoker, checking the number of arguments.
+    fn invoke(&self, receiver: *mut (), args: &[Value]) -> Result<Value, InvokeError> {
+        if self.parameters.len() != args.len() {
+            return Err(InvokeError::ArgumentCount { expected: self.parameters.len(), got: args.len() });
+        }
+
+        (self.invoker)(receiver, args.to_vec()).map_err(InvokeError::Marshall)
+    }
+}
+
+
+impl<V> Variant<V> {
+    /// Removes a field from the variant.
+    pub fn take_field(&mut self, name: &str) -> Result<V, MarshallError> {
+        match self.fields.iter().position(|field| field.0 == name) {
+            Some(index) => Ok(self.fields.remove(index).1),
+            None => Err(MarshallError::MissingField(name.to_owned())),
+        }
+    }
+}
+
+impl MarshallError {
+    /// Gets the underlying error, skipping over any collection elements.
+    pub fn innermost(&self) -> &MarshallError {
+        match *self {
+            MarshallError::Element { ref error, .. } => error.innermost(),
+            ref error => error,
+        }
+    }
 }
 
+impl fmt::Display for MarshallError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MarshallError::TypeMismatch { ref expected, ref got } => {
+                write!(fmt, "expected {}, got {}", expected, got)
+            },
+            MarshallError::OutOfRange { ref ty, ref value } => {
+                write!(fmt, "{} is out of range for {}", value, ty)
+            },
+            MarshallError::InvalidEncoding { ref encoding } => {
+                write!(fmt, "invalid byte sequence in {}", encoding)
+            },
+            MarshallError::LengthMismatch { expected, got } => {
+                write!(fmt, "expected {} elements, got {}", expected, got)
+            },
+            MarshallError::Element { index, ref error } => {
+                write!(fmt, "element {}: {}", index, error)
+            },
+            MarshallError::UnknownVariant { ref ty, ref variant } => {
+                write!(fmt, "{} has no variant named '{}'", ty, variant)
+            },
+            MarshallError::MissingField(ref name) => write!(fmt, "missing field '{}'", name),
+            MarshallError::Returned(ref message) => write!(fmt, "{}", message),
+            MarshallError::Serialization(ref message) => write!(fmt, "{}", message),
+        }
+    }
+}
+
+impl fmt::Display for InvokeError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InvokeError::UnknownMethod(ref name) => write!(fmt, "no method named '{}'", name),
+            InvokeError::WrongReceiver { ref expected, got } => {
+                write!(fmt, "expected a receiver of type {}, got {}", expected, got)
+            },
+            InvokeError::WrongStaticness { ref method, is_static: true } => {
+                write!(fmt, "'{}' is a static method", method)
+            },
+            InvokeError::WrongStaticness { ref method, is_static: false } => {
+                write!(fmt, "'{}' needs a receiver", method)
+            },
+            InvokeError::ArgumentCount { expected, got } => {
+                write!(fmt, "wrong number of arguments (given {}, expected {})", got, expected)
+            },
+            InvokeError::Marshall(ref error) => write!(fmt, "{}", error),
+        }
+    }
+}
+
+impl error::Error for InvokeError {
+    fn description(&self) -> &str {
+        match *self {
+            InvokeError::UnknownMethod(..) => "unknown method",
+            InvokeError::WrongReceiver { .. } => "wrong receiver type",
+            InvokeError::WrongStaticness { .. } => "static and instance methods mixed up",
+            InvokeError::ArgumentCount { .. } => "wrong number of arguments",
+            InvokeError::Marshall(..) => "an argument could not be marshalled",
+        }
+    }
+}
+
+impl error::Error for MarshallError {
+    fn description(&self) -> &str {
+        match *self {
+            MarshallError::TypeMismatch { .. } => "type mismatch",
+            MarshallError::OutOfRange { .. } => "number out of range",
+            MarshallError::InvalidEncoding { .. } => "invalid string encoding",
+            MarshallError::LengthMismatch { .. } => "length mismatch",
+            MarshallError::Element { .. } => "an element could not be marshalled",
+            MarshallError::UnknownVariant { .. } => "unknown enum variant",
+            MarshallError::MissingField(..) => "missing field",
+            MarshallError::Returned(..) => "the function returned an error",
+            MarshallError::Serialization(..) => "serialization failed",
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Exports class metadata as a versioned JSON document.
+//!
+//! Tools such as editor plugins can read this instead of the Rust sources,
+//! and schemas from two releases can be diffed to see how the scripting API
+//! changed.
+//!
+//! A host program can ship its own `plugger-schema` binary like so
+//!
+//! ```ignore
+//! fn main() {
+//!     plugger_core::register_class(Player::class());
+//!     plugger_core::schema::main();
+//! }
+//! ```
+
+use {Class, Constant, Field, Method, Parameter, TypeDesc};
+
+use std::fmt::Write;
+use std::{env, fs, io, process};
+
+/// The version of the schema format.
+///
+/// This is bumped whenever the format changes in a way that could break
+/// existing readers.
+pub const VERSION: u32 = 1;
+
+/// Serialises classes to a JSON schema.
+pub fn to_json(classes: &[Class]) -> String {
+    let mut classes: Vec<&Class> = classes.iter().collect();
+    // Keep the output stable so that schemas can be diffed.
+    classes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let document = Json::Object(vec![
+        ("version", Json::Number(VERSION as u64)),
+        ("classes", Json::Array(classes.into_iter().map(class).collect())),
+    ]);
+
+    let mut output = String::new();
+    document.write(&mut output, 0);
+    output.push('\n');
+    output
+}
+
+/// Serialises every registered class to a JSON schema.
+pub fn registered_to_json() -> String {
+    to_json(&::registered_classes())
+}
+
+/// The entry point of a `plugger-schema` binary.
+///
+/// Writes the schema of every registered class to the file given by
+/// `--output`, or to standard output.
+pub fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match args.iter().map(|arg| &arg[..]).collect::<Vec<_>>()[..] {
+        [] => io::Write::write_all(&mut io::stdout(), registered_to_json().as_bytes()),
+        ["--output", path] => fs::File::create(path).and_then(|mut file| {
+            io::Write::write_all(&mut file, registered_to_json().as_bytes())
+        }),
+        _ => {
+            eprintln!("usage: plugger-schema [--output <file>]");
+            process::exit(2);
+        },
+    };
+
+    if let Err(e) = result {
+        eprintln!("error: could not write schema: {}", e);
+        process::exit(1);
+    }
+}
+
+fn class(class: &Class) -> Json {
+    Json::Object(vec![
+        ("name", Json::String(class.name.clone())),
+        ("path", Json::String(class.path.clone())),
+        ("namespace", class.namespace.map(|namespace| Json::String(namespace.to_owned())).unwrap_or(Json::Null)),
+        ("fields", Json::Array(class.fields.iter().map(field).collect())),
+        ("methods", Json::Array(class.methods.iter().map(method).collect())),
+        ("constants", Json::Array(class.constants.iter().map(constant).collect())),
+        ("docs", Json::String(class.docs.to_owned())),
+    ])
+}
+
+fn field(field: &Field) -> Json {
+    Json::Object(vec![
+        ("name", Json::String(field.name.to_owned())),
+        ("type", type_desc(&field.ty)),
+        ("docs", Json::String(field.docs.to_owned())),
+    ])
+}
+
+fn method(method: &Method) -> Json {
+    Json::Object(vec![
+        ("name", Json::String(method.name.to_owned())),
+        ("static", Json::Bool(method.is_static)),
+        ("parameters", Json::Array(method.parameters.iter().map(parameter).collect())),
+        ("returns", method.ret.as_ref().map(type_desc).unwrap_or(Json::Null)),
+        ("docs", Json::String(method.docs.to_owned())),
+    ])
+}
+
+fn constant(constant: &Constant) -> Json {
+    Json::Object(vec![
+        ("name", Json::String(constant.name.to_owned())),
+        ("type", type_desc(&constant.ty)),
+        ("docs", Json::String(constant.docs.to_owned())),
+    ])
+}
+
+fn parameter(parameter: &Parameter) -> Json {
+    Json::Object(vec![
+        ("name", Json::String(parameter.name.clone())),
+        ("type", type_desc(&parameter.ty)),
+    ])
+}
+
+fn type_desc(ty: &TypeDesc) -> Json {
+    let kind = |name: &str| ("kind", Json::String(name.to_owned()));
+
+    Json::Object(match *ty {
+        TypeDesc::Unit => vec![kind("unit")],
+        TypeDesc::Primitive(name) => vec![kind("primitive"), ("name", Json::String(name.to_owned()))],
+        TypeDesc::Reference { mutable, ty: ref inner } => {
+            vec![kind("reference"), ("mutable", Json::Bool(mutable)), ("type", type_desc(inner))]
+        },
+        TypeDesc::Slice(ref elem) => vec![kind("slice"), ("element", type_desc(elem))],
+        TypeDesc::Array { ref elem, len } => {
+            vec![kind("array"), ("element", type_desc(elem)), ("length", Json::Number(len as u64))]
+        },
+        TypeDesc::Tuple(ref elems) => {
+            vec![kind("tuple"), ("elements", Json::Array(elems.iter().map(type_desc).collect()))]
+        },
+        TypeDesc::Generic { name, ref args } => vec![
+            kind("generic"),
+            ("name", Json::String(name.to_owned())),
+            ("arguments", Json::Array(args.iter().map(type_desc).collect())),
+        ],
+        TypeDesc::Class { path, .. } => vec![kind("class"), ("path", Json::String(path.to_owned()))],
+        TypeDesc::Value(path) => vec![kind("value"), ("path", Json::String(path.to_owned()))],
+        TypeDesc::Named(path) => vec![kind("named"), ("path", Json::String(path.to_owned()))],
+    })
+}
+
+/// Just enough JSON to write a schema.
+enum Json
+{
+    Null,
+    Bool(bool),
+    Number(u64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    fn write(&self, output: &mut String, indent: usize) {
+        match *self {
+            Json::Null => output.push_str("null"),
+            Json::Bool(b) => write!(output, "{}", b).unwrap(),
+            Json::Number(n) => write!(output, "{}", n).unwrap(),
+            Json::String(ref s) => write_string(output, s),
+            Json::Array(ref values) if values.is_empty() => output.push_str("[]"),
+            Json::Array(ref values) => {
+                output.push_str("[\n");
+                for (index, value) in values.iter().enumerate() {
+                    if index != 0 { output.push_str(",\n"); }
+                    write_indent(output, indent + 1);
+                    value.write(output, indent + 1);
+                }
+                output.push('\n');
+                write_indent(output, indent);
+                output.push(']');
+            },
+            Json::Object(ref pairs) => {
+                output.push_str("{\n");
+                for (index, &(key, ref value)) in pairs.iter().enumerate() {
+                    if index != 0 { output.push_str(",\n"); }
+                    write_indent(output, indent + 1);
+                    write_string(output, key);
+                    output.push_str(": ");
+                    value.write(output, indent + 1);
+                }
+                output.push('\n');
+                write_indent(output, indent);
+                output.push('}');
+            },
+        }
+    }
+}
+
+fn write_indent(output: &mut String, indent: usize) {
+    for _ in 0..indent { output.push_str("  "); }
+}
+
+fn write_string(output: &mut String, s: &str) {
+    output.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(output, "\\u{:04x}", c as u32).unwrap(),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
@@ -0,0 +1,528 @@
+//! Marshalls any `serde::Serialize`/`Deserialize` type through a `Marshall`.
+//!
+//! This lets plain data types, such as configuration structs, cross the
+//! boundary without being full pluggable classes.
+//!
+//! Values are mapped like so
+//!
+//! * structs and maps become maps, with struct fields as symbols
+//! * sequences and tuples become arrays
+//! * unit variants become symbols
+//! * other variants become a map with a single `variant => value` entry
+//!
+//! Deserialization relies on type hints from the target type, so types which
+//! need `deserialize_any` (such as untagged enums) are not supported.
+
+use {marshall_element, Marshall, MarshallError};
+
+use serde::{de, ser};
+use serde::de::IntoDeserializer;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Marshalls a `Serialize` value into a language value.
+pub fn to_value<M, T>(value: &T) -> Result<M::Value, MarshallError>
+    where M: Marshall, T: ser::Serialize + ?Sized {
+    value.serialize(Serializer::<M>(PhantomData))
+}
+
+/// Marshalls a language value into a `Deserialize` value.
+pub fn from_value<M, T>(value: M::Value) -> Result<T, MarshallError>
+    where M: Marshall, T: de::DeserializeOwned {
+    T::deserialize(Deserializer::<M> { value })
+}
+
+impl ser::Error for MarshallError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        MarshallError::Serialization(msg.to_string())
+    }
+}
+
+impl de::Error for MarshallError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        MarshallError::Serialization(msg.to_string())
+    }
+}
+
+/// Wraps a value in a single-entry map, keyed by the name of an enum variant.
+fn variant_value<M>(variant: &str, value: M::Value) -> M::Value
+    where M: Marshall {
+    M::from_map(vec![(M::from_symbol(variant.to_owned()), value)])
+}
+
+struct Serializer<M>(PhantomData<M>);
+
+/// Serializes a sequence, tuple, or tuple variant into an array.
+struct SerializeArray<M: Marshall>
+{
+    values: Vec<M::Value>,
+    variant: Option<&'static str>,
+}
+
+/// Serializes a map, struct, or struct variant into a map.
+struct SerializeMap<M: Marshall>
+{
+    pairs: Vec<(M::Value, M::Value)>,
+    key: Option<M::Value>,
+    variant: Option<&'static str>,
+}
+
+impl<M> ser::Serializer for Serializer<M>
+    where M: Marshall {
+    type Ok = M::Value;
+    type Error = MarshallError;
+
+    type SerializeSeq = SerializeArray<M>;
+    type SerializeTuple = SerializeArray<M>;
+    type SerializeTupleStruct = SerializeArray<M>;
+    type SerializeTupleVariant = SerializeArray<M>;
+    type SerializeMap = SerializeMap<M>;
+    type SerializeStruct = SerializeMap<M>;
+    type SerializeStructVariant = SerializeMap<M>;
+
+    fn serialize_bool(self, v: bool) -> Result<M::Value, MarshallError> { Ok(M::from_bool(v)) }
+    fn serialize_i8(self, v: i8) -> Result<M::Value, MarshallError> { Ok(M::from_i8(v)) }
+    fn serialize_i16(self, v: i16) -> Result<M::Value, MarshallError> { Ok(M::from_i16(v)) }
+    fn serialize_i32(self, v: i32) -> Result<M::Value, MarshallError> { Ok(M::from_i32(v)) }
+    fn serialize_i64(self, v: i64) -> Result<M::Value, MarshallError> { Ok(M::from_i64(v)) }
+    fn serialize_i128(self, v: i128) -> Result<M::Value, MarshallError> { Ok(M::from_i128(v)) }
+    fn serialize_u8(self, v: u8) -> Result<M::Value, MarshallError> { Ok(M::from_u8(v)) }
+    fn serialize_u16(self, v: u16) -> Result<M::Value, MarshallError> { Ok(M::from_u16(v)) }
+    fn serialize_u32(self, v: u32) -> Result<M::Value, MarshallError> { Ok(M::from_u32(v)) }
+    fn serialize_u64(self, v: u64) -> Result<M::Value, MarshallError> { Ok(M::from_u64(v)) }
+    fn serialize_u128(self, v: u128) -> Result<M::Value, MarshallError> { Ok(M::from_u128(v)) }
+    fn serialize_f32(self, v: f32) -> Result<M::Value, MarshallError> { Ok(M::from_f32(v)) }
+    fn serialize_f64(self, v: f64) -> Result<M::Value, MarshallError> { Ok(M::from_f64(v)) }
+    fn serialize_char(self, v: char) -> Result<M::Value, MarshallError> { Ok(M::from_char(v)) }
+    fn serialize_str(self, v: &str) -> Result<M::Value, MarshallError> { Ok(M::from_str(v)) }
+    fn serialize_bytes(self, v: &[u8]) -> Result<M::Value, MarshallError> { Ok(M::from_bytes(v)) }
+
+    fn serialize_none(self) -> Result<M::Value, MarshallError> { Ok(M::nil()) }
+    fn serialize_unit(self) -> Result<M::Value, MarshallError> { Ok(M::nil()) }
+
+    fn serialize_some<T>(self, value: &T) -> Result<M::Value, MarshallError>
+        where T: ser::Serialize + ?Sized {
+        value.serialize(self)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<M::Value, MarshallError> {
+        Ok(M::nil())
+    }
+
+    fn serialize_unit_variant(self,
+                              _name: &'static str,
+                              _index: u32,
+                              variant: &'static str) -> Result<M::Value, MarshallError> {
+        Ok(M::from_symbol(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<M::Value, MarshallError>
+        where T: ser::Serialize + ?Sized {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(self,
+                                    _name: &'static str,
+                                    _index: u32,
+                                    variant: &'static str,
+                                    value: &T) -> Result<M::Value, MarshallError>
+        where T: ser::Serialize + ?Sized {
+        Ok(variant_value::<M>(variant, to_value::<M, T>(value)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeArray<M>, MarshallError> {
+        Ok(SerializeArray { values: Vec::with_capacity(len.unwrap_or(0)), variant: None })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeArray<M>, MarshallError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeArray<M>, MarshallError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self,
+                               _name: &'static str,
+                               _index: u32,
+                               variant: &'static str,
+                               len: usize) -> Result<SerializeArray<M>, MarshallError> {
+        Ok(SerializeArray { values: Vec::with_capacity(len), variant: Some(variant) })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeMap<M>, MarshallError> {
+        Ok(SerializeMap { pairs: Vec::with_capacity(len.unwrap_or(0)), key: None, variant: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeMap<M>, MarshallError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self,
+                                _name: &'static str,
+                                _index: u32,
+                                variant: &'static str,
+                                len: usize) -> Result<SerializeMap<M>, MarshallError> {
+        Ok(SerializeMap { pairs: Vec::with_capacity(len), key: None, variant: Some(variant) })
+    }
+}
+
+impl<M: Marshall> SerializeArray<M> {
+    fn push<T>(&mut self, value: &T) -> Result<(), MarshallError>
+        where T: ser::Serialize + ?Sized {
+        let index = self.values.len();
+        let value = marshall_element(index, value, to_value::<M, T>)?;
+
+        self.values.push(value);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<M::Value, MarshallError> {
+        let array = M::from_array(self.values);
+
+        Ok(match self.variant {
+            Some(variant) => variant_value::<M>(variant, array),
+            None => array,
+        })
+    }
+}
+
+impl<M: Marshall> ser::SerializeSeq for SerializeArray<M> {
+    type Ok = M::Value;
+    type Error = MarshallError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), MarshallError>
+        where T: ser::Serialize + ?Sized { self.push(value) }
+    fn end(self) -> Result<M::Value, MarshallError> { self.finish() }
+}
+
+impl<M: Marshall> ser::SerializeTuple for SerializeArray<M> {
+    type Ok = M::Value;
+    type Error = MarshallError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), MarshallError>
+        where T: ser::Serialize + ?Sized { self.push(value) }
+    fn end(self) -> Result<M::Value, MarshallError> { self.finish() }
+}
+
+impl<M: Marshall> ser::SerializeTupleStruct for SerializeArray<M> {
+    type Ok = M::Value;
+    type Error = MarshallError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), MarshallError>
+        where T: ser::Serialize + ?Sized { self.push(value) }
+    fn end(self) -> Result<M::Value, MarshallError> { self.finish() }
+}
+
+impl<M: Marshall> ser::SerializeTupleVariant for SerializeArray<M> {
+    type Ok = M::Value;
+    type Error = MarshallError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), MarshallError>
+        where T: ser::Serialize + ?Sized { self.push(value) }
+    fn end(self) -> Result<M::Value, MarshallError> { self.finish() }
+}
+
+impl<M: Marshall> SerializeMap<M> {
+    fn field<T>(&mut self, name: &'static str, value: &T) -> Result<(), MarshallError>
+        where T: ser::Serialize + ?Sized {
+        let value = to_value::<M, T>(value)?;
+
+        self.pairs.push((M::from_symbol(name.to_owned()), value));
+        Ok(())
+    }
+
+    fn finish(self) -> Result<M::Value, MarshallError> {
+        let map = M::from_map(self.pairs);
+
+        Ok(match self.variant {
+            Some(variant) => variant_value::<M>(variant, map),
+            None => map,
+        })
+    }
+}
+
+impl<M: Marshall> ser::SerializeMap for SerializeMap<M> {
+    type Ok = M::Value;
+    type Error = MarshallError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), MarshallError>
+        where T: ser::Serialize + ?Sized {
+        self.key = Some(to_value::<M, T>(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), MarshallError>
+        where T: ser::Serialize + ?Sized {
+        let key = self.key.take().expect("serialize_value called before serialize_key");
+        let value = to_value::<M, T>(value)?;
+
+        self.pairs.push((key, value));
+        Ok(())
+    }
+
+    fn end(self) -> Result<M::Value, MarshallError> { self.finish() }
+}
+
+impl<M: Marshall> ser::SerializeStruct for SerializeMap<M> {
+    type Ok = M::Value;
+    type Error = MarshallError;
+
+    fn serialize_field<T>(&mut self, name: &'static str, value: &T) -> Result<(), MarshallError>
+        where T: ser::Serialize + ?Sized { self.field(name, value) }
+    fn end(self) -> Result<M::Value, MarshallError> { self.finish() }
+}
+
+impl<M: Marshall> ser::SerializeStructVariant for SerializeMap<M> {
+    type Ok = M::Value;
+    type Error = MarshallError;
+
+    fn serialize_field<T>(&mut self, name: &'static str, value: &T) -> Result<(), MarshallError>
+        where T: ser::Serialize + ?Sized { self.field(name, value) }
+    fn end(self) -> Result<M::Value, MarshallError> { self.finish() }
+}
+
+struct Deserializer<M: Marshall>
+{
+    value: M::Value,
+}
+
+/// Gives the elements of an array to a visitor, one at a time.
+struct ArrayAccess<M: Marshall>
+{
+    values: ::std::iter::Enumerate<::std::vec::IntoIter<M::Value>>,
+}
+
+/// Gives the entries of a map to a visitor, one at a time.
+struct MapAccess<M: Marshall>
+{
+    pairs: ::std::vec::IntoIter<(M::Value, M::Value)>,
+    value: Option<M::Value>,
+}
+
+/// Gives an enum variant to a visitor.
+struct EnumAccess<M: Marshall>
+{
+    variant: M::Value,
+    value: Option<M::Value>,
+}
+
+impl<M: Marshall> Deserializer<M> {
+    fn new(value: M::Value) -> Self { Deserializer { value } }
+}
+
+macro_rules! deserialize_basic {
+    ($($deserialize:ident => $visit:ident($to:ident)),*) => {
+        $(
+            fn $deserialize<V>(self, visitor: V) -> Result<V::Value, MarshallError>
+                where V: de::Visitor<'de> {
+                visitor.$visit(M::$to(self.value)?)
+            }
+        )*
+    }
+}
+
+impl<'de, M> de::Deserializer<'de> for Deserializer<M>
+    where M: Marshall {
+    type Error = MarshallError;
+
+    deserialize_basic! {
+        deserialize_bool => visit_bool(to_bool),
+        deserialize_i8 => visit_i8(to_i8),
+        deserialize_i16 => visit_i16(to_i16),
+        deserialize_i32 => visit_i32(to_i32),
+        deserialize_i64 => visit_i64(to_i64),
+        deserialize_i128 => visit_i128(to_i128),
+        deserialize_u8 => visit_u8(to_u8),
+        deserialize_u16 => visit_u16(to_u16),
+        deserialize_u32 => visit_u32(to_u32),
+        deserialize_u64 => visit_u64(to_u64),
+        deserialize_u128 => visit_u128(to_u128),
+        deserialize_f32 => visit_f32(to_f32),
+        deserialize_f64 => visit_f64(to_f64),
+        deserialize_char => visit_char(to_char),
+        deserialize_str => visit_string(to_string),
+        deserialize_string => visit_string(to_string),
+        deserialize_bytes => visit_byte_buf(to_bytes),
+        deserialize_byte_buf => visit_byte_buf(to_bytes),
+        deserialize_identifier => visit_string(to_symbol)
+    }
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, MarshallError>
+        where V: de::Visitor<'de> {
+        Err(MarshallError::Serialization("types without a fixed shape can not be deserialized".to_owned()))
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, MarshallError>
+        where V: de::Visitor<'de> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, MarshallError>
+        where V: de::Visitor<'de> {
+        if M::is_nil(&self.value) {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, MarshallError>
+        where V: de::Visitor<'de> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, MarshallError>
+        where V: de::Visitor<'de> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, MarshallError>
+        where V: de::Visitor<'de> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, MarshallError>
+        where V: de::Visitor<'de> {
+        let values = M::to_array(self.value)?;
+        visitor.visit_seq(ArrayAccess::<M> { values: values.into_iter().enumerate() })
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, MarshallError>
+        where V: de::Visitor<'de> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(self,
+                                   _name: &'static str,
+                                   _len: usize,
+                                   visitor: V) -> Result<V::Value, MarshallError>
+        where V: de::Visitor<'de> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, MarshallError>
+        where V: de::Visitor<'de> {
+        let pairs = M::to_map(self.value)?;
+        visitor.visit_map(MapAccess::<M> { pairs: pairs.into_iter(), value: None })
+    }
+
+    fn deserialize_struct<V>(self,
+                             _name: &'static str,
+                             _fields: &'static [&'static str],
+                             visitor: V) -> Result<V::Value, MarshallError>
+        where V: de::Visitor<'de> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V>(self,
+                           _name: &'static str,
+                           _variants: &'static [&'static str],
+                           visitor: V) -> Result<V::Value, MarshallError>
+        where V: de::Visitor<'de> {
+        // Variants with values are single-entry maps, and unit variants are
+        // plain symbols.
+        let access = match M::to_map(self.value.clone()) {
+            Ok(mut pairs) => {
+                if pairs.len() != 1 {
+                    return Err(MarshallError::LengthMismatch { expected: 1, got: pairs.len() });
+                }
+
+                let (variant, value) = pairs.remove(0);
+                EnumAccess::<M> { variant, value: Some(value) }
+            },
+            Err(..) => EnumAccess::<M> { variant: self.value, value: None },
+        };
+
+        visitor.visit_enum(access)
+    }
+}
+
+impl<'de, M> de::SeqAccess<'de> for ArrayAccess<M>
+    where M: Marshall {
+    type Error = MarshallError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, MarshallError>
+        where T: de::DeserializeSeed<'de> {
+        match self.values.next() {
+            Some((index, value)) => {
+                marshall_element(index, value, |value| seed.deserialize(Deserializer::<M>::new(value))).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> { Some(self.values.len()) }
+}
+
+impl<'de, M> de::MapAccess<'de> for MapAccess<M>
+    where M: Marshall {
+    type Error = MarshallError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, MarshallError>
+        where K: de::DeserializeSeed<'de> {
+        match self.pairs.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(Deserializer::<M>::new(key)).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, MarshallError>
+        where V: de::DeserializeSeed<'de> {
+        let value = self.value.take().expect("next_value_seed called before next_key_seed");
+        seed.deserialize(Deserializer::<M>::new(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> { Some(self.pairs.len()) }
+}
+
+impl<'de, M> de::EnumAccess<'de> for EnumAccess<M>
+    where M: Marshall {
+    type Error = MarshallError;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self), MarshallError>
+        where V: de::DeserializeSeed<'de> {
+        let name: de::value::StringDeserializer<MarshallError> =
+            M::to_symbol(self.variant.clone())?.into_deserializer();
+
+        Ok((seed.deserialize(name)?, self))
+    }
+}
+
+impl<'de, M> de::VariantAccess<'de> for EnumAccess<M>
+    where M: Marshall {
+    type Error = MarshallError;
+
+    fn unit_variant(self) -> Result<(), MarshallError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, MarshallError>
+        where T: de::DeserializeSeed<'de> {
+        seed.deserialize(Deserializer::<M>::new(self.variant_value()?))
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, MarshallError>
+        where V: de::Visitor<'de> {
+        de::Deserializer::deserialize_seq(Deserializer::<M>::new(self.variant_value()?), visitor)
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, MarshallError>
+        where V: de::Visitor<'de> {
+        de::Deserializer::deserialize_map(Deserializer::<M>::new(self.variant_value()?), visitor)
+    }
+}
+
+impl<M: Marshall> EnumAccess<M> {
+    /// Gets the value of a variant that has fields.
+    fn variant_value(self) -> Result<M::Value, MarshallError> {
+        self.value.ok_or_else(|| MarshallError::TypeMismatch {
+            expected: "a variant with fields".to_owned(),
+            got: "a unit variant".to_owned(),
+        })
+    }
+}
@@ -0,0 +1,81 @@
+//! Descriptions of the types in method signatures.
+
+use std::fmt;
+
+/// Describes a Rust type well enough for other languages and tools to
+/// reason about it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeDesc
+{
+    /// The unit type `()`.
+    Unit,
+    /// A primitive type, `str` or `String`.
+    Primitive(&'static str),
+    /// A reference `&T` or `&mut T`.
+    Reference { mutable: bool, ty: Box<TypeDesc> },
+    /// A slice `[T]`.
+    Slice(Box<TypeDesc>),
+    /// A fixed-size array `[T; N]`.
+    Array { elem: Box<TypeDesc>, len: usize },
+    /// A tuple `(A, B, ...)`.
+    Tuple(Vec<TypeDesc>),
+    /// A generic type, such as `Vec<T>`, `Option<T>` or `HashMap<K, V>`.
+    Generic { name: &'static str, args: Vec<TypeDesc> },
+    /// A pluggable class, by its fully qualified path and the namespace given
+    /// by `#[pluggable(namespace = "...")]`, if any.
+    Class { path: &'static str, namespace: Option<&'static str> },
+    /// A type marshalled by value through `PluggableValue`, by its fully
+    /// qualified path.
+    Value(&'static str),
+    /// Any other type, by its path as written.
+    Named(&'static str),
+}
+
+impl TypeDesc {
+    /// Gets the last segment of a path, or the whole name for other types.
+    pub fn short_name(&self) -> String {
+        match *self {
+            TypeDesc::Class { path, .. } | TypeDesc::Value(path) | TypeDesc::Named(path) => {
+                path.rsplit("::").next().unwrap().to_owned()
+            },
+            ref ty => ty.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for TypeDesc {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TypeDesc::Unit => write!(fmt, "()"),
+            TypeDesc::Primitive(name) => write!(fmt, "{}", name),
+            TypeDesc::Reference { mutable: true, ref ty } => write!(fmt, "&mut {}", ty),
+            TypeDesc::Reference { mutable: false, ref ty } => write!(fmt, "&{}", ty),
+            TypeDesc::Slice(ref elem) => write!(fmt, "[{}]", elem),
+            TypeDesc::Array { ref elem, len } => write!(fmt, "[{}; {}]", elem, len),
+            TypeDesc::Tuple(ref elems) => {
+                write!(fmt, "(")?;
+                write_list(fmt, elems)?;
+                // A single-element tuple needs a trailing comma.
+                if elems.len() == 1 { write!(fmt, ",")?; }
+                write!(fmt, ")")
+            },
+            TypeDesc::Generic { name, ref args } => {
+                write!(fmt, "{}<", name)?;
+                write_list(fmt, args)?;
+                write!(fmt, ">")
+            },
+            TypeDesc::Class { path, .. } | TypeDesc::Value(path) | TypeDesc::Named(path) => {
+                write!(fmt, "{}", path)
+            },
+        }
+    }
+}
+
+/// Writes a comma-separated list of types.
+fn write_list(fmt: &mut fmt::Formatter, types: &[TypeDesc]) -> fmt::Result {
+    for (index, ty) in types.iter().enumerate() {
+        if index != 0 { write!(fmt, ", ")?; }
+        write!(fmt, "{}", ty)?;
+    }
+    Ok(())
+}
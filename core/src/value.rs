@@ -0,0 +1,198 @@
+//! A value that is independent of any scripting language.
+
+use Pluggable;
+
+use std::any::TypeId;
+
+/// A language-neutral value.
+///
+/// Every `Marshall` can convert its own values to and from this type, so
+/// Rust code which inspects script results doesn't have to depend on a
+/// specific language.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value
+{
+    Nil,
+    Bool(bool),
+    /// An integer. This is wide enough for every Rust integer but `u128`.
+    Int(i128),
+    /// An integer above `i128::MAX`, which only a `u128` can hold.
+    ///
+    /// Smaller integers are always an `Int`, so that equal numbers compare
+    /// equal.
+    UInt(u128),
+    Float(f64),
+    /// A UTF-8 string.
+    String(String),
+    /// A symbol, for languages that distinguish them from strings.
+    Symbol(String),
+    /// A string of bytes, without any encoding.
+    Bytes(Vec<u8>),
+    Array(Vec<Value>),
+    /// A map, with entries in the order the language gave them.
+    Map(Vec<(Value, Value)>),
+    /// A pluggable Rust object.
+    Object(ObjectHandle),
+}
+
+/// A handle to a pluggable Rust object.
+///
+/// Handles can only be created unsafely, as anything given one can get a
+/// mutable pointer to the object through it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ObjectHandle
+{
+    /// A pointer to the object.
+    pointer: *mut (),
+    /// The identity of the object's Rust type.
+    type_id: TypeId,
+    /// The name of the object's Rust type.
+    type_name: &'static str,
+}
+
+impl Value {
+    /// Gets the name of the kind of value, for error messages.
+    pub fn kind_name(&self) -> &'static str {
+        match *self {
+            Value::Nil => "Nil",
+            Value::Bool(..) => "Bool",
+            Value::Int(..) => "Int",
+            Value::UInt(..) => "UInt",
+            Value::Float(..) => "Float",
+            Value::String(..) => "String",
+            Value::Symbol(..) => "Symbol",
+            Value::Bytes(..) => "Bytes",
+            Value::Array(..) => "Array",
+            Value::Map(..) => "Map",
+            Value::Object(ref handle) => handle.type_name,
+        }
+    }
+
+    /// Checks if the value is `Nil`.
+    pub fn is_nil(&self) -> bool {
+        matches!(*self, Value::Nil)
+    }
+
+    /// Gets the value as a boolean, if it is one.
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self { Value::Bool(b) => Some(b), _ => None }
+    }
+
+    /// Gets the value as an integer, if it is one.
+    pub fn as_int(&self) -> Option<i128> {
+        match *self { Value::Int(i) => Some(i), _ => None }
+    }
+
+    /// Gets the value as a float, if it is one.
+    pub fn as_float(&self) -> Option<f64> {
+        match *self { Value::Float(f) => Some(f), _ => None }
+    }
+
+    /// Gets the text of a string or symbol.
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref s) | Value::Symbol(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Gets the value as a byte string, if it is one.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match *self { Value::Bytes(ref bytes) => Some(bytes), _ => None }
+    }
+
+    /// Gets the elements of an array.
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match *self { Value::Array(ref values) => Some(values), _ => None }
+    }
+
+    /// Gets the entries of a map.
+    pub fn as_map(&self) -> Option<&[(Value, Value)]> {
+        match *self { Value::Map(ref pairs) => Some(pairs), _ => None }
+    }
+
+    /// Looks up an entry of a map by key.
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        self.as_map().and_then(|pairs| {
+            pairs.iter().find(|pair| pair.0 == *key).map(|pair| &pair.1)
+        })
+    }
+
+    /// Gets the object handle, if the value is an object.
+    pub fn as_object(&self) -> Option<ObjectHandle> {
+        match *self { Value::Object(handle) => Some(handle), _ => None }
+    }
+}
+
+impl ObjectHandle {
+    /// Creates a handle to an object.
+    ///
+    /// # Safety
+    ///
+    /// The object must outlive the handle and every copy of it, and must not
+    /// be otherwise borrowed while the handle is used.
+    pub unsafe fn new<T>(object: &mut T) -> Self
+        where T: Pluggable {
+        ObjectHandle {
+            pointer: object as *mut T as *mut (),
+            type_id: TypeId::of::<T>(),
+            type_name: T::type_name(),
+        }
+    }
+
+    /// Creates a handle from a pointer to an object, along with the identity
+    /// and name of its type.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must point to an object of that type, with the same
+    /// requirements as `ObjectHandle::new`.
+    pub unsafe fn from_raw_parts(pointer: *mut (), type_id: TypeId, type_name: &'static str) -> Self {
+        ObjectHandle { pointer, type_id, type_name }
+    }
+
+    /// Gets a pointer to the object.
+    pub fn pointer(&self) -> *mut () { self.pointer }
+    /// Gets the identity of the object's Rust type.
+    pub fn type_id(&self) -> TypeId { self.type_id }
+    /// Gets the name of the object's Rust type.
+    pub fn type_name(&self) -> &'static str { self.type_name }
+
+    /// Gets a pointer to the object, if it is of type `T`.
+    pub fn downcast<T>(&self) -> Option<*mut T>
+        where T: Pluggable {
+        if self.type_id == TypeId::of::<T>() {
+            Some(self.pointer as *mut T)
+        } else {
+            None
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Value { Value::Bool(b) }
+}
+
+impl From<i32> for Value {
+    fn from(i: i32) -> Value { Value::Int(i as i128) }
+}
+
+impl From<i64> for Value {
+    fn from(i: i64) -> Value { Value::Int(i as i128) }
+}
+
+impl From<f64> for Value {
+    fn from(f: f64) -> Value { Value::Float(f) }
+}
+
+impl<'a> From<&'a str> for Value {
+    fn from(s: &'a str) -> Value { Value::String(s.to_owned()) }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Value { Value::String(s) }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(values: Vec<Value>) -> Value { Value::Array(values) }
+}
@@ -0,0 +1,56 @@
+//! The pluggable types of the game.
+
+#[pluggable]
+#[derive(Debug)]
+pub struct Player {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+#[pluggable]
+#[derive(Debug)]
+pub struct Enemy {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+#[pluggable]
+impl Enemy {
+}
+
+#[pluggable]
+impl Player {
+    // FIXME: make public once callable from Ruby.
+    #[allow(dead_code)]
+    fn new() -> Self {
+        Player { x: 61.0, y: 62.0, z: 63.0 }
+    }
+
+    pub fn info(&self) -> String {
+        println!("Player at ({},{},{})", self.x, self.y, self.z);
+        "meooooooow".to_owned()
+    }
+
+    pub fn set_health(&self, health: u8) {
+        println!("setting health to '{}'", health);
+    }
+
+    pub fn set_foobar(foobar: String) {
+        println!("setting foobar to '{}'", foobar);
+    }
+
+    pub fn check(&self, other: &Player) {
+        println!("checking {:?} with {:?}", self, other);
+    }
+
+    pub fn other(&self) -> u32 {
+        12345
+    }
+
+    #[allow(dead_code)]
+    fn private_method(&self) -> f32 {
+        self.x + self.y
+    }
+}
@@ -4,70 +4,19 @@
 extern crate plugger_ruby;
 extern crate plugger_core;
 
-use std::io::Write;
-
-#[pluggable]
-#[derive(Debug)]
-pub struct Player {
-    x: f32,
-    y: f32,
-    z: f32,
-}
-
-#[pluggable]
-#[derive(Debug)]
-pub struct Enemy {
-    x: f32,
-    y: f32,
-    z: f32,
-}
-
-#[pluggable]
-impl Enemy {
-}
-
-#[pluggable]
-impl Player {
-    // FIXME: make public once callable from Ruby.
-    #[allow(dead_code)]
-    fn new() -> Self {
-        Player { x: 61.0, y: 62.0, z: 63.0 }
-    }
-
-    pub fn info(&self) -> String {
-        println!("Player at ({},{},{})", self.x, self.y, self.z);
-        "meooooooow".to_owned()
-    }
+mod game;
 
-    pub fn set_health(&self, health: u8) {
-        println!("setting health to '{}'", health);
-    }
-
-    pub fn set_foobar(foobar: String) {
-        println!("setting foobar to '{}'", foobar);
-    }
-
-    pub fn check(&self, other: &Player) {
-        println!("checking {:?} with {:?}", self, other);
-    }
-
-    pub fn other(&self) -> u32 {
-        12345
-    }
-
-    #[allow(dead_code)]
-    fn private_method(&self) -> f32 {
-        self.x + self.y
-    }
-}
+use game::{Enemy, Player};
+use std::io::Write;
 
 fn main() {
     let mut player = Player { x: 1.0, y: 2.0, z: 3.0 };
     let mut enemy = Enemy { x: 5.0, y: 3.0, z: 0.0 };
 
     let mut ruby = plugger_ruby::Ruby::new().unwrap();
-    ruby.plug("player", &mut player);
-    ruby.plug("enemy", &mut enemy);
+    ruby.set_logger(|message| println!("{}", message));
+    ruby.plug("player", &mut player).unwrap();
+    ruby.plug("enemy", &mut enemy).unwrap();
 
     loop {
         let mut line = String::new();
@@ -0,0 +1,23 @@
+//! Writes the JSON schema of the game's pluggable classes.
+//!
+//! ```text
+//! cargo run --example plugger-schema -- --output schema.json
+//! ```
+
+#![feature(plugin)]
+#![plugin(plugger)]
+
+extern crate plugger_core;
+
+#[path = "../game-logic/game.rs"]
+mod game;
+
+use game::{Enemy, Player};
+use plugger_core::Pluggable;
+
+fn main() {
+    plugger_core::register_class(Player::class());
+    plugger_core::register_class(Enemy::class());
+
+    plugger_core::schema::main();
+}
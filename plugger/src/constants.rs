@@ -0,0 +1,63 @@
+//! Plugging constants.
+//!
+//! ```ignore
+//! #[pluggable]
+//! impl Player {
+//!     pub const MAX_HEALTH: u32 = 100;
+//! }
+//! ```
+//!
+//! Every constant gets a static getter method, so it can be marshalled
+//! exactly like the return value of a method.
+
+use syntax::abi::Abi;
+use syntax::ast::{self, DUMMY_NODE_ID, Expr, Ident, ImplItem, Ty};
+use syntax::ext::base::ExtCtxt;
+use syntax::ext::build::AstBuilder;
+use syntax::codemap::{self, DUMMY_SP};
+use syntax::ptr::P;
+
+use options;
+
+/// A static method which returns the value of a constant.
+pub struct Getter
+{
+    /// The name of the constant.
+    pub constant: Ident,
+    /// The type of the constant.
+    pub ty: P<Ty>,
+    pub method: ImplItem,
+}
+
+/// Creates the getter of a constant, given an expression which reads it.
+///
+/// The getter is added to the user's own `impl`, so it is private and
+/// hidden from the docs.
+pub fn getter(ecx: &mut ExtCtxt,
+              constant: Ident,
+              ty: &P<Ty>,
+              value: P<Expr>,
+              attrs: &[ast::Attribute]) -> Getter {
+    let name = Ident::from_str(&format!("_plugger_const_{}", constant.name.as_str().to_lowercase()));
+    let mut attrs = options::docs_and_options(attrs);
+    attrs.push(quote_attr!(ecx, #[doc(hidden)]));
+
+    let method = ImplItem {
+        id: DUMMY_NODE_ID,
+        span: DUMMY_SP,
+        ident: name,
+        node: ast::ImplItemKind::Method(ast::MethodSig {
+            unsafety: ast::Unsafety::Normal,
+            constness: codemap::respan(DUMMY_SP, ast::Constness::NotConst),
+            abi: Abi::Rust,
+            decl: ecx.fn_decl(Vec::new(), ty.clone()),
+            generics: ast::Generics::default(),
+        }, ecx.block_expr(value)),
+        vis: ast::Visibility::Inherited,
+        defaultness: ast::Defaultness::Final,
+        attrs: attrs,
+        tokens: None,
+    };
+
+    Getter { constant: constant, ty: ty.clone(), method: method }
+}
@@ -0,0 +1,226 @@
+//! Plugging free functions and constants, either one at a time or a whole
+//! module at once.
+//!
+//! ```ignore
+//! #[pluggable]
+//! mod world {
+//!     pub const GRAVITY: f32 = 9.8;
+//!
+//!     pub fn spawn_enemy(x: f32, y: f32) { .. }
+//! }
+//!
+//! ruby.plug_module(&world::pluggable_module());
+//! ```
+//!
+//! The functions are wrapped as static methods of a hidden struct, so they
+//! are marshalled exactly like the methods of a pluggable `impl`. Constants
+//! get getters on the same struct.
+
+use syntax::ast::{self, DUMMY_NODE_ID, Ident, ImplItem, Item, ItemKind, MetaItem};
+use syntax::ext::base::ExtCtxt;
+use syntax::ext::build::AstBuilder;
+use syntax::codemap::DUMMY_SP;
+use syntax::ptr::P;
+use syntax::symbol::keywords;
+
+use constants::{self, Getter};
+use options::{self, ItemOptions};
+use {marshall, traits, util};
+
+/// Expands `#[pluggable]` on a free function.
+///
+/// Adds a `pluggable_<name>()` function next to it, which describes a module
+/// containing just that function.
+pub fn expand_function(ecx: &mut ExtCtxt, meta_item: &MetaItem, item: &P<Item>) -> Vec<P<Item>> {
+    let mut wrapper = match wrap_function(ecx, item) {
+        Some(wrapper) => wrapper,
+        None => return Vec::new(),
+    };
+    // The attribute being expanded holds the options of both the module and
+    // the function.
+    let (item_meta_item, method_meta_item) = options::split_item_options(meta_item);
+    let options = ItemOptions::from_meta_item(ecx, &item_meta_item);
+    wrapper.attrs.push(ecx.attribute(meta_item.span, method_meta_item));
+
+    let holder_module = Ident::from_str(&format!("_plugger_function_{}", item.ident));
+    let describe_fn = Ident::from_str(&format!("pluggable_{}", item.ident));
+
+    plug_items(ecx, holder_module, describe_fn, "", &options, vec![wrapper], Vec::new())
+}
+
+/// Expands `#[pluggable]` on a `const` or `static` item.
+///
+/// Adds a `pluggable_<name>()` function next to it, in lowercase, which
+/// describes a module containing just that constant.
+pub fn expand_constant(ecx: &mut ExtCtxt, meta_item: &MetaItem, item: &P<Item>) -> Vec<P<Item>> {
+    let mut getter = match constant_getter(ecx, item) {
+        Some(getter) => getter,
+        None => return Vec::new(),
+    };
+    // The attribute being expanded holds the options of both the module and
+    // the constant.
+    let (item_meta_item, method_meta_item) = options::split_item_options(meta_item);
+    let options = ItemOptions::from_meta_item(ecx, &item_meta_item);
+    getter.method.attrs.push(ecx.attribute(meta_item.span, method_meta_item));
+
+    let name = item.ident.name.as_str().to_lowercase();
+    let holder_module = Ident::from_str(&format!("_plugger_const_{}", name));
+    let describe_fn = Ident::from_str(&format!("pluggable_{}", name));
+
+    plug_items(ecx, holder_module, describe_fn, "", &options, Vec::new(), vec![getter])
+}
+
+/// Expands `#[pluggable]` on a module.
+///
+/// Adds a `pluggable_module()` function to the module, which describes all
+/// of its public functions.
+pub fn expand_module(ecx: &mut ExtCtxt, meta_item: &MetaItem, item: &P<Item>) -> P<Item> {
+    let options = ItemOptions::from_meta_item(ecx, meta_item);
+    let module = match item.node {
+        ItemKind::Mod(ref module) => module.clone(),
+        _ => unreachable!(),
+    };
+
+    let public_items: Vec<&P<Item>> = module.items.iter().filter(|item| item.vis == ast::Visibility::Public).collect();
+
+    let wrappers = public_items.iter().filter_map(|item| match item.node {
+        ItemKind::Fn(..) => wrap_function(ecx, item),
+        _ => None,
+    }).collect();
+    let getters = public_items.iter().filter_map(|item| match item.node {
+        ItemKind::Const(..) | ItemKind::Static(..) => constant_getter(ecx, item),
+        _ => None,
+    }).collect();
+
+    let docs = util::doc_string(&item.attrs);
+    let new_items = plug_items(ecx, Ident::from_str("_plugger_functions"),
+                               Ident::from_str("pluggable_module"), &docs, &options, wrappers, getters);
+
+    // Function options have been read, so they can be removed.
+    let items = module.items.into_iter().map(|item| P(Item {
+        attrs: options::strip_pluggable_attrs(&item.attrs),
+        ..(*item).clone()
+    })).chain(new_items).collect();
+
+    P(Item {
+        node: ItemKind::Mod(ast::Mod { items: items, ..module }),
+        ..(**item).clone()
+    })
+}
+
+/// Creates a static method that calls a free function in the parent module.
+fn wrap_function(ecx: &mut ExtCtxt, item: &Item) -> Option<ImplItem> {
+    let (decl, unsafety, constness, abi, generics) = match item.node {
+        ItemKind::Fn(ref decl, unsafety, constness, abi, ref generics, _) => (decl, unsafety, constness, abi, generics),
+        _ => return None,
+    };
+
+    let mut args = Vec::new();
+    for arg in decl.inputs.iter() {
+        if let ast::PatKind::Ident(_, ref spanned_ident, _) = arg.pat.node {
+            args.push(ecx.expr_ident(DUMMY_SP, spanned_ident.node));
+        } else {
+            ecx.span_err(arg.pat.span, "parameter names must be simple idents");
+            return None;
+        }
+    }
+
+    let function_path = ecx.path(DUMMY_SP, vec![keywords::Super.ident(), item.ident]);
+    let function_expr = ecx.expr_path(function_path);
+    let call_expr = ecx.expr_call(DUMMY_SP, function_expr, args);
+
+    Some(ImplItem {
+        id: DUMMY_NODE_ID,
+        span: item.span,
+        ident: item.ident,
+        node: ast::ImplItemKind::Method(ast::MethodSig {
+            unsafety: unsafety,
+            constness: constness,
+            abi: abi,
+            decl: decl.clone(),
+            generics: generics.clone(),
+        }, ecx.block_expr(call_expr)),
+        vis: ast::Visibility::Public,
+        defaultness: ast::Defaultness::Final,
+        attrs: options::docs_and_options(&item.attrs),
+        tokens: None,
+    })
+}
+
+/// Creates a getter which reads a `const` or `static` item in the parent module.
+fn constant_getter(ecx: &mut ExtCtxt, item: &Item) -> Option<Getter> {
+    let ty = match item.node {
+        ItemKind::Const(ref ty, _) | ItemKind::Static(ref ty, ast::Mutability::Immutable, _) => ty,
+        ItemKind::Static(..) => {
+            ecx.span_err(item.span, "mutable statics can not be pluggable");
+            return None;
+        },
+        _ => return None,
+    };
+
+    let constant_path = ecx.path(DUMMY_SP, vec![keywords::Super.ident(), item.ident]);
+    let constant_expr = ecx.expr_path(constant_path);
+
+    Some(constants::getter(ecx, item.ident, ty, constant_expr, &item.attrs))
+}
+
+/// Creates the items that plug a set of wrapped functions and constants.
+///
+/// The wrappers live in a hidden child module, which can name both
+/// `plugger_core` and everything in the parent module.
+fn plug_items(ecx: &mut ExtCtxt,
+              holder_module: Ident,
+              describe_fn: Ident,
+              docs: &str,
+              options: &ItemOptions,
+              wrappers: Vec<ImplItem>,
+              getters: Vec<Getter>) -> Vec<P<Item>> {
+    let holder = Ident::from_str("Functions");
+    let namespace = options.namespace_expr(ecx);
+    let holder_ty = ecx.ty_ident(DUMMY_SP, holder);
+
+    let getter_methods: Vec<ImplItem> = getters.iter().map(|getter| getter.method.clone()).collect();
+    let mut marshalls = marshall::create_marshalls(ecx, &wrappers);
+    marshalls.extend(marshall::create_marshalls(ecx, &getter_methods));
+    let pluggable_methods = traits::implement_pluggable_methods(ecx, &holder_ty, &wrappers, &getters).expect_item();
+
+    let impl_items = wrappers.into_iter().chain(getter_methods).map(|impl_item| ImplItem {
+        attrs: options::strip_pluggable_attrs(&impl_item.attrs),
+        ..impl_item
+    }).chain(marshalls).collect();
+    let holder_impl = ecx.item(DUMMY_SP, keywords::Invalid.ident(), Vec::new(), ItemKind::Impl(
+        ast::Unsafety::Normal,
+        ast::ImplPolarity::Positive,
+        ast::Defaultness::Final,
+        ast::Generics::default(),
+        None,
+        holder_ty,
+        impl_items,
+    ));
+
+    let holder_items = vec![
+        quote_item!(ecx, extern crate plugger_core;).unwrap(),
+        quote_item!(ecx, #[allow(unused_imports)] use super::*;).unwrap(),
+        quote_item!(ecx, pub struct Functions;).unwrap(),
+        holder_impl,
+        pluggable_methods,
+    ];
+    let holder_attrs = vec![quote_attr!(ecx, #[doc(hidden)])];
+
+    vec![
+        ecx.item_mod(DUMMY_SP, DUMMY_SP, holder_module, holder_attrs, holder_items),
+        quote_item!(ecx,
+            /// Describes the pluggable functions and constants.
+            pub fn $describe_fn() -> ::plugger_core::Module {
+                ::plugger_core::Module {
+                    name: module_path!().rsplit("::").next().unwrap().to_owned(),
+                    path: module_path!().to_owned(),
+                    namespace: $namespace,
+                    docs: $docs,
+                    functions: <$holder_module::Functions as ::plugger_core::PluggableMethods>::pluggable_methods(),
+                    constants: <$holder_module::Functions as ::plugger_core::PluggableMethods>::pluggable_constants(),
+                }
+            }
+        ).unwrap(),
+    ]
+}
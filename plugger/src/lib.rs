@@ -4,6 +4,11 @@
 //! #[pluggable]
 //! pub struct foo {
 //! }
+//!
+//! #[pluggable]
+//! mod bar {
+//!     pub fn baz() { }
+//! }
 //! ```
 
 #![feature(box_syntax)]
@@ -15,8 +20,11 @@ extern crate syntax;
 extern crate rustc;
 extern crate rustc_plugin;
 
+mod constants;
+mod functions;
 mod lang;
 mod marshall;
+mod options;
 mod traits;
 mod util;
 
@@ -50,42 +58,97 @@ pub fn registrar(reg: &mut Registry) {
 }
 
 /// If the #[pluggable] attribute is on the struct, it is plain old data with no methods.
-fn expand_pluggable_struct(ecx: &mut ExtCtxt, item: &Item) -> Vec<Annotatable> {
+fn expand_pluggable_struct(ecx: &mut ExtCtxt, meta_item: &MetaItem, item: &Item) -> Vec<Annotatable> {
     let ty = ecx.ty_ident(DUMMY_SP, item.ident);
+    let options = options::ItemOptions::from_meta_item(ecx, meta_item);
 
     let mut items = Vec::new();
     items.push(traits::implement_pluggable_fields(ecx, &ty, &item));
-    items.push(traits::implement_pluggable(ecx, &ty));
+    items.push(traits::implement_pluggable(ecx, &ty, &item, &options));
     items
 }
 
+/// If the #[pluggable] attribute is on an enum, it is marshalled by value.
+fn expand_pluggable_enum(ecx: &mut ExtCtxt, meta_item: &MetaItem, item: &Item) -> Vec<Annotatable> {
+    let ty = ecx.ty_ident(DUMMY_SP, item.ident);
+    let options = options::ItemOptions::from_meta_item(ecx, meta_item);
+
+    vec![traits::implement_pluggable_value(ecx, &ty, &item, &options)]
+}
+
 fn expand_unknown_item(ecx: &mut ExtCtxt, sp: Span) {
-    ecx.span_err(sp, "only structs and impls can be pluggable".into());
+    ecx.span_err(sp, "only structs, enums, impls, functions, constants and modules can be pluggable".into());
 }
 
-fn expand_pluggable(ecx: &mut ExtCtxt, sp: Span, _meta_item: &MetaItem, item: Annotatable) -> Vec<Annotatable> {
+fn expand_pluggable(ecx: &mut ExtCtxt, sp: Span, meta_item: &MetaItem, item: Annotatable) -> Vec<Annotatable> {
     let mut items = Vec::new();
     match item {
         Annotatable::Item(inner_item) => match inner_item.node.clone() {
             ItemKind::Struct(..) => {
                 // Push the original
                 items.push(Annotatable::Item(inner_item.clone()));
-                items.extend(expand_pluggable_struct(ecx, &inner_item))
+                items.extend(expand_pluggable_struct(ecx, meta_item, &inner_item))
+            },
+            ItemKind::Enum(..) => {
+                // Push the original
+                items.push(Annotatable::Item(inner_item.clone()));
+                items.extend(expand_pluggable_enum(ecx, meta_item, &inner_item))
+            },
+            ItemKind::Fn(..) => {
+                // Push the original
+                items.push(Annotatable::Item(inner_item.clone()));
+                items.extend(functions::expand_function(ecx, meta_item, &inner_item)
+                                 .into_iter().map(Annotatable::Item))
+            },
+            ItemKind::Const(..) | ItemKind::Static(..) => {
+                // Push the original
+                items.push(Annotatable::Item(inner_item.clone()));
+                items.extend(functions::expand_constant(ecx, meta_item, &inner_item)
+                                 .into_iter().map(Annotatable::Item))
+            },
+            ItemKind::Mod(..) => {
+                // Push the module with the wrappers attached.
+                items.push(Annotatable::Item(functions::expand_module(ecx, meta_item, &inner_item)));
             },
             ItemKind::Impl(unsafety,polarity,defaultness,generics,tref, ty, impl_items) => {
-                let pluggable_impl_items: Vec<_> = impl_items.iter().cloned()
+                // The options of the type go on the struct, and those of
+                // methods on the methods themselves.
+                if meta_item.meta_item_list().map_or(false, |list| !list.is_empty()) {
+                    ecx.span_err(meta_item.span, "options can't be given to an impl, only to its type or methods");
+                }
+
+                let (pluggable_consts, pluggable_impl_items): (Vec<_>, Vec<_>) = impl_items.iter().cloned()
                     .filter(is_pluggable_impl_item)
-                    .collect();
+                    .partition(|impl_item| match impl_item.node {
+                        ast::ImplItemKind::Const(..) => true,
+                        _ => false,
+                    });
+
+                // Constants are read through getter methods.
+                let getters: Vec<_> = pluggable_consts.iter().map(|impl_item| {
+                    let name = impl_item.ident;
+                    let ty = match impl_item.node {
+                        ast::ImplItemKind::Const(ref ty, _) => ty,
+                        _ => unreachable!(),
+                    };
+                    constants::getter(ecx, name, ty, quote_expr!(ecx, Self::$name), &impl_item.attrs)
+                }).collect();
+                let getter_methods: Vec<_> = getters.iter().map(|getter| getter.method.clone()).collect();
 
                 // Create function stubs for marshalling.
-                let new_impl_items =
+                let mut new_impl_items =
                     marshall::create_marshalls(ecx, &pluggable_impl_items);
+                new_impl_items.extend(marshall::create_marshalls(ecx, &getter_methods));
 
                 // Push the 'impl PluggableMethods' trait impl.
                 items.push(traits::implement_pluggable_methods(
-                        ecx, &ty, &pluggable_impl_items));
+                        ecx, &ty, &pluggable_impl_items, &getters));
 
-                let impl_items = impl_items.into_iter().chain(new_impl_items).collect();
+                // Method options have been read, so they can be removed.
+                let impl_items = impl_items.into_iter().chain(getter_methods).map(|impl_item| ast::ImplItem {
+                    attrs: options::strip_pluggable_attrs(&impl_item.attrs),
+                    ..impl_item
+                }).chain(new_impl_items).collect();
 
                 // Push the original item with the new impl items attached.
                 items.push(Annotatable::Item(P(Item {
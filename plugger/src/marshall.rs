@@ -1,4 +1,5 @@
 use lang::{LANGUAGES, Language};
+use options::MethodOptions;
 use util;
 
 use syntax::ptr::P;
@@ -27,8 +28,9 @@ pub fn create_marshalls(ecx: &mut ExtCtxt,
         match impl_item.node {
             ImplItemKind::Method(ref sig, ..) => {
                 let original_name = impl_item.ident;
+                let options = MethodOptions::from_attrs(ecx, &impl_item.attrs);
                 let common_marshall_fn =
-                    create_common_marshall(ecx, original_name, sig);
+                    create_common_marshall(ecx, original_name, sig, &options);
                 marshall_fns.push(common_marshall_fn);
 
                 for &lang in LANGUAGES {
@@ -49,7 +51,8 @@ pub fn create_marshalls(ecx: &mut ExtCtxt,
 /// of the `Marshall` trait.
 fn create_common_marshall(ecx: &mut ExtCtxt,
                           original_name: Ident,
-                          method_sig: &ast::MethodSig) -> ImplItem {
+                          method_sig: &ast::MethodSig,
+                          options: &MethodOptions) -> ImplItem {
     let mut marshall_sig = method_sig.clone();
 
     // Get a path to the `Marshall` trait.
@@ -92,17 +95,8 @@ fn create_common_marshall(ecx: &mut ExtCtxt,
                 _ => unimplemented!(),
             };
 
-            match util::ty_kind(&arg.ty) {
-                // A primitive type.
-                util::TypeKind::Basic { name } => {
-                    let marshall_fn = Ident::from_str(&format!("to_{}", name).to_lowercase());
-
-                    P(quote_expr!(ecx, M::$marshall_fn($arg_name)).unwrap())
-                },
-                util::TypeKind::Custom { .. } => {
-                    P(quote_expr!(ecx, &M::reference_from_value($arg_name)).unwrap())
-                }
-            }
+            let arg_expr = ecx.expr_ident(DUMMY_SP, arg_name);
+            marshall_to_rust(ecx, options, &arg.ty, arg_expr)
         }
     }).collect();
 
@@ -117,23 +111,21 @@ fn create_common_marshall(ecx: &mut ExtCtxt,
 
     // Marshall the return value if present.
     let result_expr = match method_sig.decl.output {
-        ast::FunctionRetTy::Default(..) => call_expr,
+        ast::FunctionRetTy::Default(..) => quote_expr!(ecx, { $call_expr; Ok(()) }),
         ast::FunctionRetTy::Ty(ref ty) => {
-            match util::ty_kind(&ty) {
-                // A primitive type.
-                util::TypeKind::Basic { name } => {
-                    let marshall_fn = Ident::from_str(&format!("from_{}", name).to_lowercase());
-                    quote_expr!(ecx, M::$marshall_fn($call_expr))
-                },
-                util::TypeKind::Custom { .. } => {
-                    // quote_expr!(ecx, M::reference_to_value($call_expr))
-                    unimplemented!();
-                }
-            }
-
+            let value_expr = marshall_from_rust(ecx, options, ty, call_expr);
+            quote_expr!(ecx, Ok($value_expr))
         },
     };
 
+    // The common marshall returns any errors so that each language can
+    // report them in its own way.
+    let result_ty = match method_sig.decl.output {
+        ast::FunctionRetTy::Default(..) => quote_ty!(ecx, Result<(), plugger_core::MarshallError>),
+        ast::FunctionRetTy::Ty(..) => quote_ty!(ecx, Result<M::Value, plugger_core::MarshallError>),
+    };
+    marshall_sig.decl = util::set_return_type(marshall_sig.decl, result_ty);
+
     // Add statement to call original function.
     let result_stmt = Stmt {
         id: DUMMY_NODE_ID,
@@ -185,23 +177,34 @@ fn create_lang_marshall(ecx: &mut ExtCtxt,
         }
     }).collect();
 
-    let mut stmts = Vec::new();
-
     let call_expr = ecx.expr_call(DUMMY_SP, common_marshall_expr, args);
-    let call_stmt = ecx.stmt_expr(call_expr);
-
-    stmts.push(call_stmt);
+    let marshall_ty = lang.marshall_ty(ecx);
 
-    match method_sig.decl.output {
+    // Hand any marshalling errors back to the language.
+    let result_expr = match method_sig.decl.output {
         // If we have no return type, but the language has a default value, use it.
-        ast::FunctionRetTy::Default(..) => if let Some(default_retval) = lang.default_return_value(ecx) {
-            let value_ty = lang.value_ty(ecx);
+        ast::FunctionRetTy::Default(..) => {
+            let ok_expr = if let Some(default_retval) = lang.default_return_value(ecx) {
+                let value_ty = lang.value_ty(ecx);
+
+                marshall_sig.decl = util::set_return_type(marshall_sig.decl, value_ty);
+                default_retval
+            } else {
+                quote_expr!(ecx, ())
+            };
 
-            marshall_sig.decl = util::set_return_type(marshall_sig.decl, value_ty);
-            stmts.push(ecx.stmt_expr(default_retval));
+            quote_expr!(ecx, match $call_expr {
+                Ok(()) => $ok_expr,
+                Err(e) => <$marshall_ty as plugger_core::Raise>::raise(e),
+            })
         },
-        ast::FunctionRetTy::Ty(..) => (),
-    }
+        ast::FunctionRetTy::Ty(..) => quote_expr!(ecx, match $call_expr {
+            Ok(value) => value,
+            Err(e) => <$marshall_ty as plugger_core::Raise>::raise(e),
+        }),
+    };
+
+    let stmts = vec![ecx.stmt_expr(result_expr)];
 
     let block = ecx.block(DUMMY_SP, stmts);
 
@@ -217,6 +220,283 @@ fn create_lang_marshall(ecx: &mut ExtCtxt,
     }
 }
 
+/// Creates a function which calls a method through `DynamicMarshall`.
+///
+/// The function is a `plugger_core::Invoker`, so that Rust code can call
+/// methods by name.
+pub fn dynamic_invoker(ecx: &mut ExtCtxt,
+                       ty: &P<ast::Ty>,
+                       original_name: Ident,
+                       method_sig: &ast::MethodSig) -> P<Expr> {
+    let mut marshall_path = util::ty_path(ty).clone();
+    marshall_path.segments.push(PathSegment::from_ident(config::common_marshall_name(original_name), DUMMY_SP));
+    marshall_path.segments.last_mut().unwrap().parameters = Some(P(ast::PathParameters::AngleBracketed(
+        ast::AngleBracketedParameterData {
+            span: DUMMY_SP,
+            lifetimes: Vec::new(),
+            types: vec![quote_ty!(ecx, _plugger::DynamicMarshall)],
+            bindings: Vec::new(),
+        }
+    )));
+    let marshall_expr = ecx.expr_path(marshall_path);
+
+    let mut call_args = Vec::new();
+    if method_sig.decl.has_self() {
+        call_args.push(quote_expr!(ecx, unsafe { &mut *(receiver as *mut $ty) }));
+    }
+    for _ in method_sig.decl.inputs.iter().filter(|arg| !arg.is_self()) {
+        call_args.push(quote_expr!(ecx, args.next().unwrap()));
+    }
+    let call_expr = ecx.expr_call(DUMMY_SP, marshall_expr, call_args);
+
+    let result_expr = match method_sig.decl.output {
+        ast::FunctionRetTy::Default(..) => quote_expr!(ecx, $call_expr.map(|()| _plugger::Value::Nil)),
+        ast::FunctionRetTy::Ty(..) => call_expr,
+    };
+
+    // `Class::invoke` has already checked the number of arguments.
+    quote_expr!(ecx, {
+        #[allow(unused_mut, unused_variables)]
+        fn invoke(receiver: *mut (), args: Vec<_plugger::Value>)
+            -> Result<_plugger::Value, _plugger::MarshallError> {
+            let mut args = args.into_iter();
+            $result_expr
+        }
+
+        invoke as _plugger::Invoker
+    })
+}
+
+/// Creates an expression that marshalls a language value into a Rust value of type `ty`.
+pub fn marshall_to_rust(ecx: &mut ExtCtxt, options: &MethodOptions,
+                        ty: &P<ast::Ty>, value: P<Expr>) -> P<Expr> {
+    match util::ty_kind(ty) {
+        util::TypeKind::Basic { .. } if util::is_string_ty(ty) => {
+            string_to_rust(ecx, options, value)
+        },
+        util::TypeKind::Basic { name } if name == Ident::from_str("bool") && !options.strict_bool => {
+            quote_expr!(ecx, M::is_truthy(&$value))
+        },
+        // A primitive type.
+        util::TypeKind::Basic { name } => {
+            let marshall_fn = Ident::from_str(&format!("to_{}", name).to_lowercase());
+            quote_expr!(ecx, M::$marshall_fn($value)?)
+        },
+        util::TypeKind::Unit => quote_expr!(ecx, { $value; () }),
+        util::TypeKind::Str => {
+            let string_expr = string_to_rust(ecx, options, value);
+            // The temporary `String` lives until the end of the call statement.
+            quote_expr!(ecx, &$string_expr[..])
+        },
+        util::TypeKind::CowStr => {
+            let string_expr = string_to_rust(ecx, options, value);
+            quote_expr!(ecx, ::std::borrow::Cow::Owned($string_expr))
+        },
+        util::TypeKind::Option { inner } => {
+            let inner_expr = marshall_to_rust(ecx, options, &inner, quote_expr!(ecx, value));
+
+            quote_expr!(ecx, {
+                let value = $value;
+                if M::is_nil(&value) { None } else { Some($inner_expr) }
+            })
+        },
+        util::TypeKind::Result { .. } => {
+            ecx.span_err(ty.span, "Result can only be used as a return type");
+            quote_expr!(ecx, unreachable!())
+        },
+        util::TypeKind::Vec { elem } => {
+            let elem_expr = marshall_to_rust(ecx, options, &elem, quote_expr!(ecx, value));
+
+            quote_expr!(ecx,
+                plugger_core::marshall_elements(M::to_array($value)?, |value| Ok($elem_expr))?
+            )
+        },
+        util::TypeKind::Slice { elem } => {
+            let elem_expr = marshall_to_rust(ecx, options, &elem, quote_expr!(ecx, value));
+
+            // The temporary `Vec` lives until the end of the call statement.
+            quote_expr!(ecx,
+                &plugger_core::marshall_elements(M::to_array($value)?, |value| Ok($elem_expr))?
+            )
+        },
+        util::TypeKind::Bytes => quote_expr!(ecx, M::to_bytes($value)?),
+        // The temporary `Vec` lives until the end of the call statement.
+        util::TypeKind::ByteSlice => quote_expr!(ecx, &M::to_bytes($value)?[..]),
+        util::TypeKind::Array { elem, len } => {
+            let len = match util::integer_literal(&len) {
+                Some(len) => len,
+                None => {
+                    ecx.span_err(len.span, "array lengths must be integer literals");
+                    return quote_expr!(ecx, unreachable!());
+                },
+            };
+            let elem_expr = marshall_to_rust(ecx, options, &elem, quote_expr!(ecx, value));
+            let element_exprs = (0..len).map(|_| {
+                quote_expr!(ecx, elements.next().unwrap())
+            }).collect();
+            let array_expr = ecx.expr_vec(DUMMY_SP, element_exprs);
+
+            quote_expr!(ecx, {
+                let values = M::to_array($value)?;
+                if values.len() != $len {
+                    return Err(plugger_core::MarshallError::LengthMismatch {
+                        expected: $len,
+                        got: values.len(),
+                    });
+                }
+
+                let mut elements = plugger_core::marshall_elements(values, |value| Ok($elem_expr))?.into_iter();
+                $array_expr
+            })
+        },
+        util::TypeKind::Map { key: key_ty, value: value_ty } => {
+            let key_expr = if options.symbol_keys && util::is_string_ty(&key_ty) {
+                quote_expr!(ecx, M::to_symbol(key)?)
+            } else {
+                marshall_to_rust(ecx, options, &key_ty, quote_expr!(ecx, key))
+            };
+            let value_expr = marshall_to_rust(ecx, options, &value_ty, quote_expr!(ecx, value));
+
+            quote_expr!(ecx,
+                plugger_core::marshall_elements(M::to_map($value)?, |(key, value)| {
+                    Ok(($key_expr, $value_expr))
+                })?.into_iter().collect()
+            )
+        },
+        util::TypeKind::Tuple { elems } => {
+            let len = elems.len();
+            let elem_exprs = elems.iter().enumerate().map(|(index, elem)| {
+                let elem_expr = marshall_to_rust(ecx, options, elem, quote_expr!(ecx, value));
+                quote_expr!(ecx,
+                    plugger_core::marshall_element($index, values.next().unwrap(), |value| Ok($elem_expr))?
+                )
+            }).collect();
+            let tuple_expr = ecx.expr_tuple(DUMMY_SP, elem_exprs);
+
+            quote_expr!(ecx, {
+                let values = M::to_array($value)?;
+                if values.len() != $len {
+                    return Err(plugger_core::MarshallError::LengthMismatch {
+                        expected: $len,
+                        got: values.len(),
+                    });
+                }
+
+                let mut values = values.into_iter();
+                $tuple_expr
+            })
+        },
+        util::TypeKind::Reference { .. } => {
+            quote_expr!(ecx, M::reference_from_value($value)?)
+        },
+        util::TypeKind::Custom { .. } if options.serde => {
+            quote_expr!(ecx, plugger_core::serialization::from_value::<M, $ty>($value)?)
+        },
+        util::TypeKind::Custom { .. } => {
+            quote_expr!(ecx, <$ty as plugger_core::PluggableValue>::from_value::<M>($value)?)
+        },
+    }
+}
+
+/// Creates an expression that marshalls a language value into a `String`.
+fn string_to_rust(ecx: &mut ExtCtxt, options: &MethodOptions, value: P<Expr>) -> P<Expr> {
+    if options.coerce_symbols {
+        // `to_symbol` accepts both symbols and strings.
+        quote_expr!(ecx, M::to_symbol($value)?)
+    } else {
+        quote_expr!(ecx, M::to_string($value)?)
+    }
+}
+
+/// Creates an expression that marshalls a Rust value of type `ty` into a language value.
+///
+/// This may `return` early from the marshall function (or from the closure
+/// marshalling a collection element) if a Rust `Result` is an error.
+pub fn marshall_from_rust(ecx: &mut ExtCtxt, options: &MethodOptions,
+                          ty: &P<ast::Ty>, expr: P<Expr>) -> P<Expr> {
+    match util::ty_kind(ty) {
+        // A primitive type.
+        util::TypeKind::Basic { name } => {
+            let marshall_fn = Ident::from_str(&format!("from_{}", name).to_lowercase());
+            quote_expr!(ecx, M::$marshall_fn($expr))
+        },
+        util::TypeKind::Unit => quote_expr!(ecx, { $expr; M::nil() }),
+        util::TypeKind::Str => quote_expr!(ecx, M::from_str($expr)),
+        util::TypeKind::CowStr => quote_expr!(ecx, M::from_str(&$expr)),
+        util::TypeKind::Option { inner } => {
+            let inner_expr = marshall_from_rust(ecx, options, &inner, quote_expr!(ecx, value));
+
+            quote_expr!(ecx, match $expr {
+                Some(value) => $inner_expr,
+                None => M::nil(),
+            })
+        },
+        util::TypeKind::Result { ok } => {
+            let ok_expr = marshall_from_rust(ecx, options, &ok, quote_expr!(ecx, value));
+
+            quote_expr!(ecx, match $expr {
+                Ok(value) => $ok_expr,
+                Err(e) => return Err(plugger_core::MarshallError::Returned(e.to_string())),
+            })
+        },
+        util::TypeKind::Vec { elem } => {
+            let elem_expr = marshall_from_rust(ecx, options, &elem, quote_expr!(ecx, value));
+
+            quote_expr!(ecx,
+                M::from_array(plugger_core::marshall_elements($expr, |value| Ok($elem_expr))?)
+            )
+        },
+        util::TypeKind::Bytes => quote_expr!(ecx, M::from_bytes(&$expr)),
+        util::TypeKind::ByteSlice => quote_expr!(ecx, M::from_bytes($expr)),
+        util::TypeKind::Slice { elem } | util::TypeKind::Array { elem, .. } => {
+            let elem_expr = marshall_from_rust(ecx, options, &elem, quote_expr!(ecx, value));
+
+            quote_expr!(ecx,
+                M::from_array(plugger_core::marshall_elements($expr.iter().cloned(), |value| Ok($elem_expr))?)
+            )
+        },
+        util::TypeKind::Map { key: key_ty, value: value_ty } => {
+            let key_expr = if options.symbol_keys && util::is_string_ty(&key_ty) {
+                quote_expr!(ecx, M::from_symbol(key))
+            } else {
+                marshall_from_rust(ecx, options, &key_ty, quote_expr!(ecx, key))
+            };
+            let value_expr = marshall_from_rust(ecx, options, &value_ty, quote_expr!(ecx, value));
+
+            quote_expr!(ecx,
+                M::from_map(plugger_core::marshall_elements($expr, |(key, value)| {
+                    Ok(($key_expr, $value_expr))
+                })?)
+            )
+        },
+        util::TypeKind::Tuple { elems } => {
+            let elem_exprs = elems.iter().enumerate().map(|(index, elem)| {
+                let field_expr = ecx.expr_tup_field_access(DUMMY_SP, quote_expr!(ecx, tuple), index);
+                let elem_expr = marshall_from_rust(ecx, options, elem, quote_expr!(ecx, value));
+                quote_expr!(ecx,
+                    plugger_core::marshall_element($index, $field_expr, |value| Ok($elem_expr))?
+                )
+            }).collect();
+            let values_expr = ecx.expr_vec(DUMMY_SP, elem_exprs);
+
+            quote_expr!(ecx, {
+                let tuple = $expr;
+                M::from_array($values_expr.to_vec())
+            })
+        },
+        util::TypeKind::Reference { .. } => {
+            // quote_expr!(ecx, M::reference_to_value($expr))
+            unimplemented!();
+        },
+        util::TypeKind::Custom { .. } if options.serde => {
+            quote_expr!(ecx, plugger_core::serialization::to_value::<M, $ty>(&$expr)?)
+        },
+        util::TypeKind::Custom { .. } => {
+            quote_expr!(ecx, plugger_core::PluggableValue::to_value::<M>($expr)?)
+        },
+    }
+}
+
 mod config {
     use lang::Language;
     use syntax::ast::{self, Expr, Ident, Path};
@@ -0,0 +1,174 @@
+//! Options that can be given to `#[pluggable(...)]` on a method, or on a
+//! struct, enum or module.
+//!
+//! ```ignore
+//! #[pluggable(namespace = "Game")]
+//! pub struct Player { .. }
+//!
+//! #[pluggable]
+//! impl Player {
+//!     #[pluggable(symbol_keys)]
+//!     pub fn stats(&self) -> HashMap<String, u32> { .. }
+//! }
+//! ```
+
+use syntax::ast;
+use syntax::codemap::{DUMMY_SP, Span};
+use syntax::ext::base::ExtCtxt;
+use syntax::ext::build::AstBuilder;
+use syntax::ptr::P;
+use syntax::symbol::Symbol;
+
+/// The marshalling options for a single method.
+#[derive(Clone, Default)]
+pub struct MethodOptions
+{
+    /// Whether `String` keys of maps should be mapped to symbols.
+    pub symbol_keys: bool,
+    /// Whether symbols should be accepted for `String`, `&str` and `Cow<str>`
+    /// parameters.
+    pub coerce_symbols: bool,
+    /// Whether `bool` parameters must be given exactly `true` or `false`,
+    /// rather than any value the language considers true.
+    pub strict_bool: bool,
+    /// Whether types other than the built-in ones should be marshalled through
+    /// `serde`, rather than `PluggableValue`.
+    ///
+    /// Requires the `serde` feature of `plugger-core`.
+    pub serde: bool,
+}
+
+impl MethodOptions
+{
+    /// Reads the options from the `#[pluggable(...)]` attributes of a method.
+    pub fn from_attrs(ecx: &mut ExtCtxt, attrs: &[ast::Attribute]) -> Self {
+        let (options, unknown_options) = MethodOptions::parse(attrs);
+
+        for span in unknown_options {
+            ecx.span_err(span, "unknown pluggable option");
+        }
+        options
+    }
+
+    /// Reads the options, along with the spans of any unknown options.
+    ///
+    /// Use this rather than `from_attrs` when the same attributes have
+    /// already been checked, so that errors are only reported once.
+    pub fn parse(attrs: &[ast::Attribute]) -> (Self, Vec<Span>) {
+        let mut options = MethodOptions::default();
+        let mut unknown_options = Vec::new();
+
+        for attr in attrs.iter().filter(|attr| is_pluggable_attr(attr)) {
+            for item in attr.meta_item_list().unwrap_or_default() {
+                if item.check_name("symbol_keys") {
+                    options.symbol_keys = true;
+                } else if item.check_name("coerce_symbols") {
+                    options.coerce_symbols = true;
+                } else if item.check_name("strict_bool") {
+                    options.strict_bool = true;
+                } else if item.check_name("serde") {
+                    options.serde = true;
+                } else {
+                    unknown_options.push(item.span);
+                }
+            }
+        }
+
+        (options, unknown_options)
+    }
+
+    /// Creates a `plugger_core::MethodOptions` expression for the options.
+    pub fn options_expr(&self, ecx: &mut ExtCtxt) -> P<ast::Expr> {
+        let (symbol_keys, coerce_symbols) = (self.symbol_keys, self.coerce_symbols);
+        let (strict_bool, serde) = (self.strict_bool, self.serde);
+
+        quote_expr!(ecx, _plugger::MethodOptions {
+            symbol_keys: $symbol_keys,
+            coerce_symbols: $coerce_symbols,
+            strict_bool: $strict_bool,
+            serde: $serde,
+        })
+    }
+}
+
+/// The options for a struct, enum or module.
+#[derive(Clone, Default)]
+pub struct ItemOptions
+{
+    /// The namespace to define the item in, such as `Game::World`, rather
+    /// than the one derived from its module path.
+    ///
+    /// An empty namespace means the top level.
+    pub namespace: Option<String>,
+}
+
+impl ItemOptions
+{
+    /// Reads the options from the `#[pluggable(...)]` attribute of an item.
+    pub fn from_meta_item(ecx: &mut ExtCtxt, meta_item: &ast::MetaItem) -> Self {
+        let mut options = ItemOptions::default();
+
+        for item in meta_item.meta_item_list().unwrap_or(&[]) {
+            if item.check_name("namespace") {
+                match item.value_str() {
+                    Some(namespace) if is_valid_namespace(&namespace.as_str()) => {
+                        options.namespace = Some(namespace.as_str().to_string());
+                    },
+                    _ => ecx.span_err(item.span, "namespaces must be strings of `::`-separated CamelCase names"),
+                }
+            } else {
+                ecx.span_err(item.span, "unknown pluggable option");
+            }
+        }
+        options
+    }
+
+    /// Creates an `Option<&'static str>` expression for the namespace.
+    pub fn namespace_expr(&self, ecx: &mut ExtCtxt) -> P<ast::Expr> {
+        match self.namespace {
+            Some(ref namespace) => {
+                let namespace_expr = ecx.expr_str(DUMMY_SP, Symbol::intern(namespace));
+                ecx.expr_some(DUMMY_SP, namespace_expr)
+            },
+            None => ecx.expr_none(DUMMY_SP),
+        }
+    }
+}
+
+/// Splits the `#[pluggable(...)]` attribute of a free function or constant
+/// into the options of the module it is plugged in as, and its own options.
+pub fn split_item_options(meta_item: &ast::MetaItem) -> (ast::MetaItem, ast::MetaItem) {
+    let (item_options, method_options): (Vec<_>, Vec<_>) = meta_item.meta_item_list().unwrap_or(&[]).iter().cloned()
+        .partition(|item| item.check_name("namespace"));
+
+    (ast::MetaItem { node: ast::MetaItemKind::List(item_options), ..meta_item.clone() },
+     ast::MetaItem { node: ast::MetaItemKind::List(method_options), ..meta_item.clone() })
+}
+
+fn is_valid_namespace(namespace: &str) -> bool {
+    namespace.is_empty() || namespace.split("::").all(|name| {
+        name.chars().next().map_or(false, char::is_uppercase) &&
+            name.chars().all(|c| c.is_alphanumeric() || c == '_')
+    })
+}
+
+/// Checks if an attribute is `#[pluggable]`.
+pub fn is_pluggable_attr(attr: &ast::Attribute) -> bool {
+    attr.check_name("pluggable")
+}
+
+/// Removes any `#[pluggable(...)]` attributes.
+///
+/// These must be removed from methods once we have read them, otherwise the
+/// compiler would try to expand them as items in their own right.
+pub fn strip_pluggable_attrs(attrs: &[ast::Attribute]) -> Vec<ast::Attribute> {
+    attrs.iter().filter(|attr| !is_pluggable_attr(attr)).cloned().collect()
+}
+
+/// Keeps only the doc comments and `#[pluggable(...)]` attributes.
+///
+/// Used when generating methods from other items, which need the docs and
+/// the options but none of the other attributes.
+pub fn docs_and_options(attrs: &[ast::Attribute]) -> Vec<ast::Attribute> {
+    attrs.iter().filter(|attr| attr.check_name("doc") || is_pluggable_attr(attr)).cloned().collect()
+}
@@ -1,14 +1,18 @@
-use syntax::ast::{self, Expr, ImplItem, Item, ItemKind, Ty, VariantData};
+use syntax::ast::{self, Expr, ImplItem, Item, ItemKind, Stmt, Ty, VariantData};
 use syntax::ext::base::{ExtCtxt,Annotatable};
 use syntax::ext::build::AstBuilder;
-use syntax::codemap::DUMMY_SP;
+use syntax::codemap::{DUMMY_SP, Spanned};
 use syntax::ptr::P;
 
+use constants::Getter;
+use options::{ItemOptions, MethodOptions};
 use {marshall, util};
 
-pub fn implement_pluggable(ecx: &mut ExtCtxt, ty: &P<Ty>) -> Annotatable {
+pub fn implement_pluggable(ecx: &mut ExtCtxt, ty: &P<Ty>, item: &Item, options: &ItemOptions) -> Annotatable {
     let ty_path = util::ty_path(ty);
     let ty_name = util::ty_name_str(ty).as_str();
+    let docs = util::doc_string(&item.attrs);
+    let namespace = options.namespace_expr(ecx);
 
     let dummy_const = ecx.ident_of(&format!("_IMPL_PLUGGABLE_FOR_{}", ty_name));
 
@@ -18,7 +22,10 @@ pub fn implement_pluggable(ecx: &mut ExtCtxt, ty: &P<Ty>) -> Annotatable {
             extern crate plugger_core as _plugger;
 
             impl _plugger::Pluggable for $ty_path {
-                fn name(&self) -> &'static str { $ty_name }
+                fn type_name() -> &'static str { $ty_name }
+                fn type_path() -> &'static str { concat!(module_path!(), "::", $ty_name) }
+                fn type_docs() -> &'static str { $docs }
+                fn type_namespace() -> Option<&'static str> { $namespace }
             }
         };
     ).unwrap())
@@ -29,7 +36,7 @@ pub fn implement_pluggable_fields(ecx: &mut ExtCtxt, ty: &P<Ty>, item: &Item) ->
     let ty_name = util::ty_name_str(ty);
     let dummy_const = ecx.ident_of(&format!("_IMPL_PLUGGABLE_FIELDS_FOR_{}", ty_name));
 
-    let fields = pluggable_struct_fields(ecx, item);
+    let fields = pluggable_struct_fields(ecx, ty, item);
     let fields_body = ecx.expr_vec(DUMMY_SP, fields);
 
     Annotatable::Item(quote_item!(ecx,
@@ -38,7 +45,7 @@ pub fn implement_pluggable_fields(ecx: &mut ExtCtxt, ty: &P<Ty>, item: &Item) ->
             extern crate plugger_core as _plugger;
 
             impl _plugger::PluggableFields for $ty_path {
-                fn pluggable_fields(&self) -> Vec<_plugger::Field> {
+                fn pluggable_fields() -> Vec<_plugger::Field> {
                     $fields_body.iter().cloned().collect()
                 }
             }
@@ -46,13 +53,16 @@ pub fn implement_pluggable_fields(ecx: &mut ExtCtxt, ty: &P<Ty>, item: &Item) ->
     ).unwrap())
 }
 
-pub fn implement_pluggable_methods(ecx: &mut ExtCtxt, ty: &P<Ty>, impl_items: &[ImplItem]) -> Annotatable {
+pub fn implement_pluggable_methods(ecx: &mut ExtCtxt, ty: &P<Ty>, impl_items: &[ImplItem],
+                                   getters: &[Getter]) -> Annotatable {
     let ty_path = util::ty_path(ty);
     let ty_name = util::ty_name_str(ty);
     let dummy_const = ecx.ident_of(&format!("_IMPL_PLUGGABLE_METHODS_FOR_{}", ty_name));
 
     let methods = pluggable_struct_methods(ecx, ty, impl_items);
     let methods_body = ecx.expr_vec(DUMMY_SP, methods);
+    let constants = pluggable_constants(ecx, ty, getters);
+    let constants_body = ecx.expr_vec(DUMMY_SP, constants);
 
     Annotatable::Item(quote_item!(ecx,
         #[allow(non_upper_case_globals)]
@@ -60,94 +70,399 @@ pub fn implement_pluggable_methods(ecx: &mut ExtCtxt, ty: &P<Ty>, impl_items: &[
             extern crate plugger_core as _plugger;
 
             impl _plugger::PluggableMethods for $ty_path {
-                fn pluggable_methods(&self) -> Vec<_plugger::Method> {
+                fn pluggable_methods() -> Vec<_plugger::Method> {
                     $methods_body.iter().cloned().collect()
                 }
+
+                fn pluggable_constants() -> Vec<_plugger::Constant> {
+                    $constants_body.iter().cloned().collect()
+                }
             }
         };
     ).unwrap())
 }
 
-fn pluggable_struct_fields(_ecx: &mut ExtCtxt, struct_item: &Item) -> Vec<P<Expr>> {
-    match struct_item.node {
-        ItemKind::Struct(VariantData::Struct(ref _fields, _), _) => {
-            Vec::new() // FIXME: unimplemented
-        },
+/// Implements `PluggableValue` for an enum.
+///
+/// Enums where no variant has fields are marshalled as symbols, all
+/// other enums are marshalled through `Marshall::from_variant`.
+pub fn implement_pluggable_value(ecx: &mut ExtCtxt, ty: &P<Ty>, item: &Item, options: &ItemOptions) -> Annotatable {
+    let ty_path = util::ty_path(ty);
+    let ty_name = util::ty_name_str(ty);
+    let namespace = options.namespace_expr(ecx);
+    let dummy_const = ecx.ident_of(&format!("_IMPL_PLUGGABLE_VALUE_FOR_{}", ty_name));
+
+    let variants = match item.node {
+        ItemKind::Enum(ref enum_def, _) => enum_def.variants.clone(),
         _ => unreachable!(),
-    }
+    };
+    let ty_name_str = ty_name.as_str();
+    let is_fieldless = variants.iter().all(|variant| variant.node.data.fields().is_empty());
+
+    let (to_value_body, from_value_body) = if is_fieldless {
+        (fieldless_enum_to_value(ecx, ty, &variants), fieldless_enum_from_value(ecx, ty, &variants))
+    } else {
+        (data_enum_to_value(ecx, ty, &variants), data_enum_from_value(ecx, ty, &variants))
+    };
+
+    Annotatable::Item(quote_item!(ecx,
+        #[allow(non_upper_case_globals)]
+        const $dummy_const: () = {
+            extern crate plugger_core as _plugger;
+
+            impl _plugger::PluggableValue for $ty_path {
+                fn type_name() -> &'static str { $ty_name_str }
+                fn type_path() -> &'static str { concat!(module_path!(), "::", $ty_name_str) }
+                fn type_namespace() -> Option<&'static str> { $namespace }
+
+                fn to_value<M>(self) -> Result<M::Value, _plugger::MarshallError>
+                    where M: _plugger::Marshall {
+                    $to_value_body
+                }
+
+                fn from_value<M>(value: M::Value) -> Result<Self, _plugger::MarshallError>
+                    where M: _plugger::Marshall {
+                    $from_value_body
+                }
+            }
+        };
+    ).unwrap())
 }
 
-fn pluggable_struct_methods(ecx: &mut ExtCtxt, ty: &P<Ty>, impl_items: &[ImplItem]) -> Vec<P<Expr>> {
-    impl_items.iter().filter_map(|impl_item| {
-        match impl_item.node {
-            ast::ImplItemKind::Method(ref sig, _) => {
-                let mut method_path = util::ty_path(ty).clone();
-                method_path.segments.push(ast::PathSegment {
-                    identifier: impl_item.ident,
-                    span: DUMMY_SP,
-                    parameters: None,
-                });
+/// Gets the path to an enum variant.
+fn variant_path(ty: &P<Ty>, variant: &ast::Variant) -> ast::Path {
+    let mut path = util::ty_path(ty).clone();
+    path.segments.push(ast::PathSegment::from_ident(variant.node.name, DUMMY_SP));
+    path
+}
 
-                let method_expr = ecx.expr_path(method_path);
-                let method_name = ecx.expr_str(DUMMY_SP, impl_item.ident.name);
+/// Gets the names of the fields of a variant, along with their types.
+///
+/// Tuple fields are named `field0`, `field1`, etc.
+fn variant_fields(variant: &ast::Variant) -> Vec<(ast::Ident, P<Ty>)> {
+    variant.node.data.fields().iter().enumerate().map(|(index, field)| {
+        let name = field.ident.unwrap_or_else(|| ast::Ident::from_str(&format!("field{}", index)));
+        (name, field.ty.clone())
+    }).collect()
+}
 
-                let is_static = !sig.decl.has_self();
+fn fieldless_enum_to_value(ecx: &mut ExtCtxt, ty: &P<Ty>, variants: &[ast::Variant]) -> P<Expr> {
+    let arms = variants.iter().map(|variant| {
+        let pat = ecx.pat_path(DUMMY_SP, variant_path(ty, variant));
+        let name = util::snake_case(variant.node.name);
+        ecx.arm(DUMMY_SP, vec![pat], quote_expr!(ecx, Ok(M::from_symbol($name.to_owned()))))
+    }).collect();
 
-                let return_type = match sig.decl.output {
-                    ast::FunctionRetTy::Default(..) => {
-                        ecx.expr_none(DUMMY_SP)
-                    },
-                    ast::FunctionRetTy::Ty(ref ty) => {
-                        let expr_str = ecx.expr_str(DUMMY_SP, util::ty_name_str(ty));
-                        ecx.expr_some(DUMMY_SP, expr_str)
+    ecx.expr_match(DUMMY_SP, quote_expr!(ecx, self), arms)
+}
+
+fn fieldless_enum_from_value(ecx: &mut ExtCtxt, ty: &P<Ty>, variants: &[ast::Variant]) -> P<Expr> {
+    let ty_name = util::ty_name_str(ty).as_str();
+
+    let mut stmts: Vec<Stmt> = vec![quote_stmt!(ecx, let name = M::to_symbol(value)?;).unwrap()];
+    stmts.extend(variants.iter().map(|variant| {
+        let name = util::snake_case(variant.node.name);
+        let variant_expr = ecx.expr_path(variant_path(ty, variant));
+        quote_stmt!(ecx, if name == $name { return Ok($variant_expr); }).unwrap()
+    }));
+
+    let error_expr = quote_expr!(ecx,
+        Err(_plugger::MarshallError::UnknownVariant { ty: $ty_name.to_owned(), variant: name })
+    );
+    ecx.expr_block(ecx.block(DUMMY_SP, stmts.into_iter().chain(Some(ecx.stmt_expr(error_expr))).collect()))
+}
+
+fn data_enum_to_value(ecx: &mut ExtCtxt, ty: &P<Ty>, variants: &[ast::Variant]) -> P<Expr> {
+    let options = MethodOptions::default();
+
+    let arms = variants.iter().map(|variant| {
+        let path = variant_path(ty, variant);
+        let fields = variant_fields(variant);
+
+        // Bind every field to a variable of the same name.
+        let pat = match variant.node.data {
+            VariantData::Struct(..) => {
+                let field_pats = fields.iter().map(|&(name, _)| Spanned {
+                    node: ast::FieldPat {
+                        ident: name,
+                        pat: ecx.pat_ident(DUMMY_SP, name),
+                        is_shorthand: true,
+                        attrs: ast::ThinVec::new(),
                     },
-                };
+                    span: DUMMY_SP,
+                }).collect();
+                ecx.pat_struct(DUMMY_SP, path, field_pats)
+            },
+            VariantData::Tuple(..) => {
+                let field_pats = fields.iter().map(|&(name, _)| ecx.pat_ident(DUMMY_SP, name)).collect();
+                ecx.pat_tuple_struct(DUMMY_SP, path, field_pats)
+            },
+            VariantData::Unit(..) => ecx.pat_path(DUMMY_SP, path),
+        };
+
+        let field_exprs = fields.iter().map(|&(name, ref field_ty)| {
+            let name_str = name.name.as_str();
+            let field_expr = ecx.expr_ident(DUMMY_SP, name);
+            let value_expr = marshall::marshall_from_rust(ecx, &options, field_ty, field_expr);
+            quote_expr!(ecx, ($name_str.to_owned(), $value_expr))
+        }).collect();
+        let fields_expr = ecx.expr_vec(DUMMY_SP, field_exprs);
+        let name = util::snake_case(variant.node.name);
 
-                let mut inputs = sig.decl.inputs.iter();
+        ecx.arm(DUMMY_SP, vec![pat], quote_expr!(ecx,
+            Ok(M::from_variant::<Self>(_plugger::Variant {
+                name: $name.to_owned(),
+                fields: $fields_expr.to_vec(),
+            }))
+        ))
+    }).collect();
 
-                // Eat 'self' if it exists.
-                if !is_static { inputs.next().unwrap(); }
+    ecx.expr_match(DUMMY_SP, quote_expr!(ecx, self), arms)
+}
 
-                let parameters = ecx.expr_vec(DUMMY_SP, inputs.filter_map(|arg| {
-                    let name_expr = if let ast::PatKind::Ident(_, ref spanned_ident, _) = arg.pat.node {
-                        ecx.expr_str(DUMMY_SP, spanned_ident.node.name)
-                    } else {
-                        ecx.span_err(arg.pat.span, "parameter names must be simple idents");
-                        return None;
-                    };
+fn data_enum_from_value(ecx: &mut ExtCtxt, ty: &P<Ty>, variants: &[ast::Variant]) -> P<Expr> {
+    let ty_name = util::ty_name_str(ty).as_str();
+    let options = MethodOptions::default();
 
-                    let ty_expr = ecx.expr_str(DUMMY_SP, util::ty_name_str(&arg.ty));
+    let mut stmts: Vec<Stmt> = vec![
+        quote_stmt!(ecx, let mut variant = M::to_variant::<Self>(value)?;).unwrap(),
+    ];
+    stmts.extend(variants.iter().map(|variant| {
+        let path = variant_path(ty, variant);
+        let name = util::snake_case(variant.node.name);
 
-                    Some(quote_expr!(ecx,
-                        _plugger::Parameter {
-                            name: $name_expr.to_owned(),
-                            ty: $ty_expr,
-                        }
-                    ))
-                }).collect());
+        let field_exprs: Vec<_> = variant_fields(variant).into_iter().map(|(field_name, field_ty)| {
+            let field_name_str = field_name.name.as_str();
+            let value_expr = quote_expr!(ecx, variant.take_field($field_name_str)?);
+            (field_name, marshall::marshall_to_rust(ecx, &options, &field_ty, value_expr))
+        }).collect();
 
-                let lang_marshalls = marshall::lang_marshalls(ecx, &impl_item).into_iter().map(|(lang,marshall_path)| {
-                    quote_expr!(ecx, ($lang, $marshall_path as *mut _))
+        let variant_expr = match variant.node.data {
+            VariantData::Struct(..) => {
+                let fields = field_exprs.into_iter().map(|(field_name, expr)| {
+                    ecx.field_imm(DUMMY_SP, field_name, expr)
                 }).collect();
-                let lang_marshalls = ecx.expr_vec(DUMMY_SP, lang_marshalls);
-
-                Some(quote_expr!(ecx,
-                    _plugger::Method {
-                        method_pointer: $method_expr as *mut _,
-                        lang_marshalls: $lang_marshalls.to_vec(),
-                        name: $method_name,
-                        parameters: $parameters.iter().cloned().collect(),
-                        ret: $return_type,
-                        is_static: $is_static,
-                    }
-                ))
+                ecx.expr_struct(DUMMY_SP, path, fields)
+            },
+            VariantData::Tuple(..) => {
+                let args = field_exprs.into_iter().map(|(_, expr)| expr).collect();
+                let constructor = ecx.expr_path(path);
+                ecx.expr_call(DUMMY_SP, constructor, args)
             },
+            VariantData::Unit(..) => ecx.expr_path(path),
+        };
+
+        quote_stmt!(ecx, if variant.name == $name { return Ok($variant_expr); }).unwrap()
+    }));
+
+    let error_expr = quote_expr!(ecx,
+        Err(_plugger::MarshallError::UnknownVariant { ty: $ty_name.to_owned(), variant: variant.name })
+    );
+    ecx.expr_block(ecx.block(DUMMY_SP, stmts.into_iter().chain(Some(ecx.stmt_expr(error_expr))).collect()))
+}
+
+/// Describes the public fields of a struct.
+fn pluggable_struct_fields(ecx: &mut ExtCtxt, ty: &P<Ty>, struct_item: &Item) -> Vec<P<Expr>> {
+    let fields = match struct_item.node {
+        ItemKind::Struct(VariantData::Struct(ref fields, _), _) => fields,
+        ItemKind::Struct(..) => return Vec::new(),
+        _ => unreachable!(),
+    };
+    let ty_path = util::ty_path(ty);
+
+    fields.iter().filter(|field| field.vis == ast::Visibility::Public).map(|field| {
+        let name = field.ident.unwrap();
+        let name_str = name.name.as_str();
+        let docs = util::doc_string(&field.attrs);
+        // Fields aren't marshalled, so they can be of any type.
+        let ty_expr = type_desc(ecx, &MethodOptions::default(), &field.ty, false);
+
+        quote_expr!(ecx,
+            _plugger::Field {
+                field_offset: unsafe { &(*::std::ptr::null::<$ty_path>()).$name as *const _ as usize },
+                ty: $ty_expr,
+                name: $name_str,
+                docs: $docs,
+            }
+        )
+    }).collect()
+}
+
+fn pluggable_struct_methods(ecx: &mut ExtCtxt, ty: &P<Ty>, impl_items: &[ImplItem]) -> Vec<P<Expr>> {
+    impl_items.iter().filter_map(|impl_item| {
+        match impl_item.node {
+            ast::ImplItemKind::Method(ref sig, _) => Some(pluggable_method(ecx, ty, impl_item, sig)),
             _ => {
-                ecx.span_err(impl_item.span, "only works on methods");
+                ecx.span_err(impl_item.span, "only works on methods and constants");
                 None
             }
         }
+    }).collect()
+}
+
+fn pluggable_constants(ecx: &mut ExtCtxt, ty: &P<Ty>, getters: &[Getter]) -> Vec<P<Expr>> {
+    getters.iter().map(|getter| {
+        let sig = match getter.method.node {
+            ast::ImplItemKind::Method(ref sig, _) => sig,
+            _ => unreachable!(),
+        };
+
+        let getter_expr = pluggable_method(ecx, ty, &getter.method, sig);
+        let name = ecx.expr_str(DUMMY_SP, getter.constant.name);
+        let docs = util::doc_string(&getter.method.attrs);
+
+        let (options, _) = MethodOptions::parse(&getter.method.attrs);
+        let ty_expr = type_desc(ecx, &options, &getter.ty, true);
 
+        quote_expr!(ecx,
+            _plugger::Constant {
+                name: $name,
+                ty: $ty_expr,
+                docs: $docs,
+                getter: $getter_expr,
+            }
+        )
     }).collect()
 }
 
+/// Creates an expression that describes a method.
+fn pluggable_method(ecx: &mut ExtCtxt, ty: &P<Ty>, impl_item: &ImplItem, sig: &ast::MethodSig) -> P<Expr> {
+    let mut method_path = util::ty_path(ty).clone();
+    method_path.segments.push(ast::PathSegment {
+        identifier: impl_item.ident,
+        span: DUMMY_SP,
+        parameters: None,
+    });
+
+    let method_expr = ecx.expr_path(method_path);
+    let method_name = ecx.expr_str(DUMMY_SP, impl_item.ident.name);
+
+    let is_static = !sig.decl.has_self();
+    let docs = util::doc_string(&impl_item.attrs);
+
+    // Unknown options are reported when the marshalls are created.
+    let (options, _) = MethodOptions::parse(&impl_item.attrs);
+
+    let return_type = match sig.decl.output {
+        ast::FunctionRetTy::Default(..) => {
+            ecx.expr_none(DUMMY_SP)
+        },
+        ast::FunctionRetTy::Ty(ref ty) => {
+            let desc_expr = type_desc(ecx, &options, ty, true);
+            ecx.expr_some(DUMMY_SP, desc_expr)
+        },
+    };
+
+    let mut inputs = sig.decl.inputs.iter();
+
+    // Eat 'self' if it exists.
+    if !is_static { inputs.next().unwrap(); }
+
+    let parameters = ecx.expr_vec(DUMMY_SP, inputs.filter_map(|arg| {
+        let name_expr = if let ast::PatKind::Ident(_, ref spanned_ident, _) = arg.pat.node {
+            ecx.expr_str(DUMMY_SP, spanned_ident.node.name)
+        } else {
+            ecx.span_err(arg.pat.span, "parameter names must be simple idents");
+            return None;
+        };
+
+        let ty_expr = type_desc(ecx, &options, &arg.ty, true);
+
+        Some(quote_expr!(ecx,
+            _plugger::Parameter {
+                name: $name_expr.to_owned(),
+                ty: $ty_expr,
+            }
+        ))
+    }).collect());
+
+    let lang_marshalls = marshall::lang_marshalls(ecx, impl_item).into_iter().map(|(lang,marshall_path)| {
+        quote_expr!(ecx, ($lang, $marshall_path as *mut _))
+    }).collect();
+    let lang_marshalls = ecx.expr_vec(DUMMY_SP, lang_marshalls);
+    let invoker = marshall::dynamic_invoker(ecx, ty, impl_item.ident, sig);
+    let options_expr = options.options_expr(ecx);
+
+    quote_expr!(ecx,
+        _plugger::Method {
+            method_pointer: $method_expr as *mut _,
+            lang_marshalls: $lang_marshalls.to_vec(),
+            invoker: $invoker,
+            name: $method_name,
+            parameters: $parameters.iter().cloned().collect(),
+            ret: $return_type,
+            is_static: $is_static,
+            docs: $docs,
+            options: $options_expr,
+        }
+    )
+}
+
+/// Creates an expression that builds a `TypeDesc` for a type.
+///
+/// `marshalled` is whether values of the type are marshalled. Other types,
+/// such as those of fields, needn't implement any plugger traits, so custom
+/// types among them are only described by name.
+fn type_desc(ecx: &mut ExtCtxt, options: &MethodOptions, ty: &P<Ty>, marshalled: bool) -> P<Expr> {
+    match ty.node {
+        ast::TyKind::Path(_, ref path) => {
+            let name = path.segments.last().unwrap().identifier.name.as_str();
+            let args = util::generic_args(path);
+
+            if util::is_primitive_ty(ty) {
+                quote_expr!(ecx, _plugger::TypeDesc::Primitive($name))
+            } else if !args.is_empty() {
+                // Errors are only ever converted into strings, so they can be
+                // of any type.
+                let is_result = name == "Result";
+                let arg_exprs = args.iter().enumerate().map(|(index, arg)| {
+                    type_desc(ecx, options, arg, marshalled && !(is_result && index == 1))
+                }).collect();
+                let args_expr = ecx.expr_vec(DUMMY_SP, arg_exprs);
+                quote_expr!(ecx, _plugger::TypeDesc::Generic { name: $name, args: $args_expr.to_vec() })
+            } else if options.serde || !marshalled {
+                let path_str = ::syntax::print::pprust::path_to_string(path);
+                quote_expr!(ecx, _plugger::TypeDesc::Named($path_str))
+            } else {
+                quote_expr!(ecx, _plugger::TypeDesc::Value(<$ty as _plugger::PluggableValue>::type_path()))
+            }
+        },
+        ast::TyKind::Rptr(_, ref mut_ty) => {
+            let mutable = mut_ty.mutbl == ast::Mutability::Mutable;
+            let inner = &mut_ty.ty;
+
+            let inner_expr = match inner.node {
+                ast::TyKind::Path(..) if marshalled && !util::is_primitive_ty(inner) => {
+                    quote_expr!(ecx, _plugger::TypeDesc::Class {
+                        path: <$inner as _plugger::Pluggable>::type_path(),
+                        namespace: <$inner as _plugger::Pluggable>::type_namespace(),
+                    })
+                },
+                _ => type_desc(ecx, options, inner, marshalled),
+            };
+            quote_expr!(ecx, _plugger::TypeDesc::Reference { mutable: $mutable, ty: Box::new($inner_expr) })
+        },
+        ast::TyKind::Slice(ref elem) => {
+            let elem_expr = type_desc(ecx, options, elem, marshalled);
+            quote_expr!(ecx, _plugger::TypeDesc::Slice(Box::new($elem_expr)))
+        },
+        ast::TyKind::Array(ref elem, ref len) => {
+            let elem_expr = type_desc(ecx, options, elem, marshalled);
+            let len = util::integer_literal(len).unwrap_or_else(|| {
+                ecx.span_err(len.span, "array lengths must be integer literals");
+                0
+            });
+            quote_expr!(ecx, _plugger::TypeDesc::Array { elem: Box::new($elem_expr), len: $len })
+        },
+        ast::TyKind::Tup(ref tys) if tys.is_empty() => quote_expr!(ecx, _plugger::TypeDesc::Unit),
+        ast::TyKind::Tup(ref tys) => {
+            let elem_exprs = tys.iter().map(|elem| type_desc(ecx, options, elem, marshalled)).collect();
+            let elems_expr = ecx.expr_vec(DUMMY_SP, elem_exprs);
+            quote_expr!(ecx, _plugger::TypeDesc::Tuple($elems_expr.to_vec()))
+        },
+        _ => {
+            ecx.span_err(ty.span, "unsupported type");
+            quote_expr!(ecx, _plugger::TypeDesc::Unit)
+        },
+    }
+}
@@ -1,12 +1,17 @@
 use syntax::ptr::P;
 use syntax::ast::{self, Name, Ty,TyKind,Path};
+use syntax::parse::lexer::comments;
 
 const BASIC_TYPES: &'static [&'static str] = &[
     "bool",
-    "u8", "u16", "u32", "u64",
-    "i8", "i16", "i32", "i64",
+    "u8", "u16", "u32", "u64", "u128", "usize",
+    "i8", "i16", "i32", "i64", "i128", "isize",
     "f32", "f64",
-    "String",
+    "char", "String",
+];
+
+const MAP_TYPES: &'static [&'static str] = &[
+    "HashMap", "BTreeMap",
 ];
 
 /// Gets the path to a type.
@@ -60,6 +65,33 @@ pub fn set_return_type(decl: P<ast::FnDecl>, new_ty: P<ast::Ty>) -> P<ast::FnDec
 
 pub enum TypeKind {
     Basic { name: ast::Ident },
+    /// The unit type `()`.
+    Unit,
+    /// An `Option<T>`, which maps to either `nil` or a value.
+    Option { inner: P<Ty> },
+    /// A `Result<T, E>`. Only supported for return values.
+    Result { ok: P<Ty> },
+    /// A `Vec<T>`, which maps to an array.
+    Vec { elem: P<Ty> },
+    /// A slice `&[T]`, which maps to an array.
+    Slice { elem: P<Ty> },
+    /// A `Vec<u8>`, which maps to a binary string.
+    Bytes,
+    /// A byte slice `&[u8]`, which maps to a binary string.
+    ByteSlice,
+    /// A fixed-size array `[T; N]`.
+    Array { elem: P<Ty>, len: P<ast::Expr> },
+    /// A `HashMap<K, V>` or `BTreeMap<K, V>`, which maps to a hash.
+    Map { key: P<Ty>, value: P<Ty> },
+    /// A borrowed string `&str`.
+    Str,
+    /// A `Cow<str>`.
+    CowStr,
+    /// A tuple, which maps to an array.
+    Tuple { elems: Vec<P<Ty>> },
+    /// A reference to a pluggable object.
+    Reference { ty: P<Ty> },
+    /// A type that is marshalled by value through `PluggableValue`.
     Custom { name: ast::Ident },
 }
 
@@ -67,17 +99,131 @@ pub fn ty_kind(ty: &P<Ty>) -> TypeKind {
     match ty.node {
         ast::TyKind::Path(_, ref path) => {
             let ident = path.segments.last().unwrap().identifier;
+            let mut args = generic_args(path);
 
             if BASIC_TYPES.iter().map(|pt| ast::Ident::from_str(pt)).any(|pt| pt == ident) {
                 TypeKind::Basic { name: ident }
+            } else if ident == ast::Ident::from_str("Option") && args.len() == 1 {
+                TypeKind::Option { inner: args.remove(0) }
+            } else if ident == ast::Ident::from_str("Result") && args.len() == 2 {
+                TypeKind::Result { ok: args.remove(0) }
+            } else if ident == ast::Ident::from_str("Cow") && args.len() == 1 && is_str_ty(&args[0]) {
+                TypeKind::CowStr
+            } else if ident == ast::Ident::from_str("Vec") && args.len() == 1 && is_u8_ty(&args[0]) {
+                TypeKind::Bytes
+            } else if ident == ast::Ident::from_str("Vec") && args.len() == 1 {
+                TypeKind::Vec { elem: args.remove(0) }
+            } else if MAP_TYPES.iter().any(|&mt| ident == ast::Ident::from_str(mt)) && args.len() == 2 {
+                TypeKind::Map { key: args.remove(0), value: args.remove(0) }
             } else {
                 TypeKind::Custom { name: ident }
             }
         },
-        ast::TyKind::Rptr(_, ref _ty) => {
-            TypeKind::Custom { name: ast::Ident::from_str("foobarpleaseset") }
+        ast::TyKind::Rptr(_, ref mut_ty) => match mut_ty.ty.node {
+            ast::TyKind::Slice(ref elem) if is_u8_ty(elem) => TypeKind::ByteSlice,
+            ast::TyKind::Slice(ref elem) => TypeKind::Slice { elem: elem.clone() },
+            _ if is_str_ty(&mut_ty.ty) => TypeKind::Str,
+            _ => TypeKind::Reference { ty: mut_ty.ty.clone() },
+        },
+        ast::TyKind::Array(ref elem, ref len) => {
+            TypeKind::Array { elem: elem.clone(), len: len.clone() }
         },
+        ast::TyKind::Tup(ref tys) if tys.is_empty() => TypeKind::Unit,
+        ast::TyKind::Tup(ref tys) => TypeKind::Tuple { elems: tys.clone() },
         ref kind => panic!("unknown type kind: '{:?}'", kind),
     }
 }
 
+/// Checks if a type is `String`.
+pub fn is_string_ty(ty: &P<Ty>) -> bool {
+    match ty_kind(ty) {
+        TypeKind::Basic { name } => name == ast::Ident::from_str("String"),
+        _ => false,
+    }
+}
+
+/// Checks if a type is a primitive type, `str` or `String`.
+pub fn is_primitive_ty(ty: &P<Ty>) -> bool {
+    match ty.node {
+        ast::TyKind::Path(None, ref path) => {
+            let ident = path.segments.last().unwrap().identifier;
+            generic_args(path).is_empty() &&
+                (is_str_ty(ty) || BASIC_TYPES.iter().any(|&pt| ident == ast::Ident::from_str(pt)))
+        },
+        _ => false,
+    }
+}
+
+/// Checks if a type is `str`.
+fn is_str_ty(ty: &P<Ty>) -> bool {
+    match ty.node {
+        ast::TyKind::Path(None, ref path) => {
+            path.segments.len() == 1 &&
+                path.segments[0].identifier == ast::Ident::from_str("str")
+        },
+        _ => false,
+    }
+}
+
+/// Checks if a type is `u8`.
+fn is_u8_ty(ty: &P<Ty>) -> bool {
+    match ty_kind(ty) {
+        TypeKind::Basic { name } => name == ast::Ident::from_str("u8"),
+        _ => false,
+    }
+}
+
+/// Gets the generic type arguments of the last segment of a path.
+pub fn generic_args(path: &Path) -> Vec<P<Ty>> {
+    match path.segments.last().unwrap().parameters {
+        Some(ref parameters) => match **parameters {
+            ast::PathParameters::AngleBracketed(ref data) => data.types.clone(),
+            _ => Vec::new(),
+        },
+        None => Vec::new(),
+    }
+}
+
+/// Gets the value of an integer literal expression.
+pub fn integer_literal(expr: &P<ast::Expr>) -> Option<usize> {
+    match expr.node {
+        ast::ExprKind::Lit(ref lit) => match lit.node {
+            ast::LitKind::Int(value, _) => Some(value as usize),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Converts a `CamelCase` identifier into `snake_case`.
+pub fn snake_case(ident: ast::Ident) -> String {
+    let mut result = String::new();
+
+    for (i, c) in ident.name.as_str().chars().enumerate() {
+        if c.is_uppercase() {
+            if i != 0 { result.push('_'); }
+            result.extend(c.to_lowercase());
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Gets the documentation of an item from its doc comments and `#[doc]`
+/// attributes.
+pub fn doc_string(attrs: &[ast::Attribute]) -> String {
+    let docs: Vec<String> = attrs.iter().filter(|attr| attr.check_name("doc")).filter_map(|attr| {
+        attr.value_str().map(|value| if attr.is_sugared_doc {
+            comments::strip_doc_comment_decoration(&value.as_str())
+        } else {
+            value.as_str().to_string()
+        })
+    }).collect();
+
+    // Doc comments are usually written with a space after the slashes.
+    let lines: Vec<&str> = docs.iter().flat_map(|doc| doc.lines()).map(|line| {
+        if line.starts_with(' ') { &line[1..] } else { line }
+    }).collect();
+    lines.join("\n").trim().to_owned()
+}
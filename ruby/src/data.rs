@@ -0,0 +1,159 @@
+//! Native data wrappers for Rust pointers.
+//!
+//! Pointers are never handed to Ruby as plain integers. Instead objects are
+//! stored as typed data, which the VM checks every time we unwrap it, so
+//! a script can not forge an object. Function pointers are never handed to
+//! Ruby at all, and are looked up by the method being called instead.
+
+use ffi;
+use shims::Receiver;
+
+use plugger_core::{MarshallError, ObjectHandle, Pluggable};
+use rurust::Value;
+use libc;
+use std::any::TypeId;
+use std::collections::BTreeMap;
+use std::ptr;
+use std::sync::Mutex;
+
+/// A Rust object that has been plugged into Ruby.
+pub struct Object {
+    /// A pointer to the object.
+    pub pointer: *mut Receiver,
+    /// The identity of the object's Rust type.
+    pub type_id: TypeId,
+    /// The name of the object's Rust type.
+    pub type_name: &'static str,
+}
+
+/// A Rust function that has been plugged into Ruby.
+pub struct Function {
+    /// A pointer to the language-specific marshall function.
+    pub pointer: *mut fn(),
+    /// The number of arguments the function takes, excluding the receiver.
+    pub arg_count: usize,
+    /// Whether the function is called without a receiver.
+    pub is_static: bool,
+    /// The identity and name of the Rust type that the function is a method
+    /// of, or `None` for the functions of a module.
+    pub receiver: Option<(TypeId, String)>,
+}
+
+// Functions are never changed once they are registered.
+unsafe impl Send for Function { }
+unsafe impl Sync for Function { }
+
+/// Plugged functions, by the class or module and the method symbol they are
+/// defined as.
+type FunctionTable = BTreeMap<(ffi::INNER_VALUE, ffi::INNER_VALUE), &'static Function>;
+
+/// Every plugged function.
+///
+/// Functions are never freed, as the classes they are defined on are never
+/// collected.
+static FUNCTIONS: Mutex<FunctionTable> = Mutex::new(BTreeMap::new());
+
+/// A `rb_data_type_t` that can be stored in a static.
+struct DataType(ffi::rb_data_type_t);
+
+unsafe impl Sync for DataType { }
+
+/// The data type of every plugged Rust object.
+static OBJECT_TYPE: DataType = DataType(ffi::rb_data_type_t {
+    wrap_struct_name: b"plugger_object\0" as *const u8 as *const libc::c_char,
+    function: ffi::rb_data_type_function_t {
+        dmark: None,
+        // Only the handle is freed, the object itself is owned by Rust.
+        dfree: Some(free_object),
+        dsize: None,
+        reserved: [ptr::null_mut(); 2],
+    },
+    parent: ptr::null(),
+    data: ptr::null_mut(),
+    flags: ffi::VALUE(0),
+});
+
+/// Wraps a pointer to a Rust object in a new instance of `class`.
+pub fn wrap_object<P>(class: Value, object: &mut P) -> Value
+    where P: Pluggable {
+    // The object is owned by Rust, which must keep it alive for as long as
+    // Ruby can reach it.
+    wrap_handle(class, unsafe { ObjectHandle::new(object) })
+}
+
+/// Wraps a handle to a Rust object in a new instance of `class`.
+pub fn wrap_handle(class: Value, handle: ObjectHandle) -> Value {
+    let object = Box::into_raw(Box::new(Object {
+        pointer: handle.pointer() as *mut Receiver,
+        type_id: handle.type_id(),
+        type_name: handle.type_name(),
+    }));
+
+    Value::from(unsafe {
+        ffi::rb_data_typed_object_wrap(class.0, object as *mut _, &OBJECT_TYPE.0)
+    })
+}
+
+/// Gets a handle to the Rust object that a Ruby object wraps, if any.
+pub fn object_handle(value: Value) -> Option<ObjectHandle> {
+    if is_object(value) {
+        let object = unwrap_object(value);
+
+        Some(unsafe { ObjectHandle::from_raw_parts(object.pointer as *mut (), object.type_id, object.type_name) })
+    } else {
+        None
+    }
+}
+
+/// Checks if a value is a plugged Rust object.
+fn is_object(value: Value) -> bool {
+    unsafe { ffi::rb_typeddata_is_kind_of(value.0, &OBJECT_TYPE.0) != 0 }
+}
+
+/// Gets the Rust object that a Ruby object wraps.
+///
+/// Raises a `TypeError` if the value is not a plugged object.
+pub fn unwrap_object<'a>(value: Value) -> &'a Object {
+    unsafe { &*(ffi::rb_check_typeddata(value.0, &OBJECT_TYPE.0) as *const Object) }
+}
+
+/// Gets the Rust object of type `T` that a Ruby object wraps.
+pub fn unwrap_object_of<T>(value: Value) -> Result<*mut T, MarshallError>
+    where T: Pluggable {
+    let actual_type_name = if is_object(value) {
+        let object = unwrap_object(value);
+
+        if object.type_id == TypeId::of::<T>() {
+            return Ok(object.pointer as *mut T);
+        }
+        object.type_name.to_owned()
+    } else {
+        value.class_name()
+    };
+
+    Err(MarshallError::TypeMismatch {
+        expected: T::type_name().to_owned(),
+        got: actual_type_name,
+    })
+}
+
+/// Registers the Rust function that implements a method of a class or module.
+///
+/// A function that is already registered for the method is kept, as plugging
+/// the same module again gives the same functions.
+pub fn register_function(class: Value, method: &str, function: Function) {
+    let key = (class.0 .0, Value::symbol(method).0 .0);
+
+    FUNCTIONS.lock().unwrap().entry(key).or_insert_with(|| Box::leak(Box::new(function)));
+}
+
+/// Gets the Rust function that implements a method of a class or module.
+pub fn function(class: Value, method: Value) -> Option<&'static Function> {
+    FUNCTIONS.lock().unwrap().get(&(class.0 .0, method.0 .0)).cloned()
+}
+
+unsafe extern "C" fn free_object(object: *mut libc::c_void) {
+    if !object.is_null() {
+        drop(Box::from_raw(object as *mut Object));
+    }
+}
@@ -0,0 +1,56 @@
+//! Raising and catching Ruby exceptions from Rust.
+
+use ffi;
+
+use rurust::{self, Value};
+
+/// Raises an exception of the given class.
+///
+/// This unwinds straight back into the Ruby VM, so nothing on the
+/// current Rust stack frame will be dropped. The message is copied into a
+/// Ruby string and dropped first, but callers must not own anything else.
+pub fn raise<S>(class: Value, message: S) -> ! where S: Into<String> {
+    let message = message.into();
+    let exception = unsafe {
+        let message = ffi::rb_utf8_str_new(message.as_ptr() as *const _, message.len() as _);
+        ffi::rb_exc_new_str(class.0, message)
+    };
+    drop(message);
+
+    unsafe { ffi::rb_exc_raise(exception) }
+}
+
+/// Calls a function, catching any exception that Ruby raises inside it.
+///
+/// Exceptions unwind past Rust stack frames without dropping anything, so
+/// Ruby code that may raise must be called through this while any locks are
+/// held. Anything owned by `f` itself is leaked if an exception is raised.
+pub fn protect<F, T>(f: F) -> Result<T, rurust::ErrorKind>
+    where F: FnOnce() -> T {
+    struct Call<F, T> {
+        f: Option<F>,
+        result: Option<T>,
+    }
+
+    extern "C" fn call_function<F, T>(data: ffi::VALUE) -> ffi::VALUE
+        where F: FnOnce() -> T {
+        let call = unsafe { &mut *(data.0 as *mut Call<F, T>) };
+        call.result = Some((call.f.take().unwrap())());
+        ffi::Qnil
+    }
+
+    let mut call = Call { f: Some(f), result: None };
+    let data = ffi::VALUE(&mut call as *mut Call<F, T> as ffi::INNER_VALUE);
+    let mut state = 0;
+
+    unsafe { ffi::rb_protect(call_function::<F, T>, data, &mut state) };
+
+    if state == 0 {
+        Ok(call.result.unwrap())
+    } else {
+        let exception = Value::from(unsafe { ffi::rb_errinfo() });
+        unsafe { ffi::rb_set_errinfo(ffi::Qnil) };
+
+        Err(rurust::ErrorKind::Exception(exception))
+    }
+}
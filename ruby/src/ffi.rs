@@ -0,0 +1,68 @@
+//! Ruby C API definitions that are missing from `mri-sys`.
+
+#![allow(non_camel_case_types)]
+
+pub use mri_sys::*;
+
+use libc;
+
+/// A function that is called by the garbage collector with a wrapped pointer.
+pub type RUBY_DATA_FUNC = Option<unsafe extern "C" fn(*mut libc::c_void)>;
+
+#[repr(C)]
+pub struct rb_data_type_function_t {
+    pub dmark: RUBY_DATA_FUNC,
+    pub dfree: RUBY_DATA_FUNC,
+    pub dsize: Option<unsafe extern "C" fn(*const libc::c_void) -> libc::size_t>,
+    pub reserved: [*mut libc::c_void; 2],
+}
+
+/// Describes a type of native data that can be wrapped in a Ruby object.
+#[repr(C)]
+pub struct rb_data_type_t {
+    pub wrap_struct_name: *const libc::c_char,
+    pub function: rb_data_type_function_t,
+    pub parent: *const rb_data_type_t,
+    pub data: *mut libc::c_void,
+    pub flags: VALUE,
+}
+
+extern "C" {
+    /// Wraps a pointer in a new object of class `klass`.
+    pub fn rb_data_typed_object_wrap(klass: VALUE,
+                                     datap: *mut libc::c_void,
+                                     ty: *const rb_data_type_t) -> VALUE;
+
+    /// Gets the pointer wrapped by an object.
+    ///
+    /// Raises a `TypeError` if the object does not wrap data of the given type.
+    pub fn rb_check_typeddata(obj: VALUE, ty: *const rb_data_type_t) -> *mut libc::c_void;
+
+    /// Creates a binary (`ASCII-8BIT`) string from a buffer.
+    pub fn rb_str_new(ptr: *const libc::c_char, len: libc::c_long) -> VALUE;
+
+    /// Creates a `UTF-8` string from a buffer.
+    pub fn rb_utf8_str_new(ptr: *const libc::c_char, len: libc::c_long) -> VALUE;
+
+    /// Checks if an object wraps data of the given type.
+    pub fn rb_typeddata_is_kind_of(obj: VALUE, ty: *const rb_data_type_t) -> libc::c_int;
+
+    /// Gets the name of the method that is currently executing, as it was
+    /// defined rather than as it was called.
+    pub fn rb_frame_this_func() -> ID;
+
+    /// Gets the class of an object, skipping any singleton class.
+    pub fn rb_obj_class(obj: VALUE) -> VALUE;
+
+    /// Gets the superclass of a class, or `nil` for `BasicObject`.
+    pub fn rb_class_superclass(klass: VALUE) -> VALUE;
+
+    /// Creates an exception of class `etype` with a message.
+    pub fn rb_exc_new_str(etype: VALUE, message: VALUE) -> VALUE;
+
+    /// Raises an exception object.
+    pub fn rb_exc_raise(exception: VALUE) -> !;
+
+    /// Keeps an object alive for the lifetime of the VM.
+    pub fn rb_gc_register_mark_object(obj: VALUE);
+}
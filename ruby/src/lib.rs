@@ -1,27 +1,56 @@
 extern crate plugger_core;
 extern crate rurust;
+extern crate mri_sys;
 extern crate libc;
 
 pub use rurust::Value as Value;
 
 /// Shim functions which act as middlemen between C and Ruby.
 pub mod shims;
+/// Generation of RBS signatures and YARD stubs for tooling.
+pub mod signatures;
 
 // Must be public so that the plugger crate can use.
 #[doc(hidden)]
 pub use self::marshall::Marshall;
 
 mod marshall;
+mod naming;
+mod data;
+mod reflection;
+mod error;
+mod ffi;
 
 /// The Ruby support code.
-const RUBY_SUPPORT: &'static str = include_str!("../support/ruby.rb");
+const RUBY_SUPPORT: &str = include_str!("../support/ruby.rb");
 /// The base class all Ruby plugger objects derive from.
-const PLUGGER_BASE_CLASS: &'static str = "PluggerObject";
+const PLUGGER_BASE_CLASS: &str = "PluggerObject";
+/// The base class of all data-carrying Rust enums.
+const PLUGGER_ENUM_CLASS: &str = "PluggerEnum";
+/// The module that plugged classes and modules are extended with.
+const PLUGGER_REFLECTION_MODULE: &str = "PluggerReflection";
+/// The module that runs scripts with plugged local variables.
+const PLUGGER_SCRIPT_MODULE: &str = "PluggerScript";
+/// The reserved words of Ruby that could otherwise be local variable names.
+const RUBY_KEYWORDS: &[&str] = &[
+    "__ENCODING__", "__FILE__", "__LINE__", "alias", "and", "begin", "break", "case", "class", "def",
+    "do", "else", "elsif", "end", "ensure", "false", "for", "if", "in", "module", "next",
+    "nil", "not", "or", "redo", "rescue", "retry", "return", "self", "super", "then", "true", "undef",
+    "unless", "until", "when", "while", "yield",
+];
 
-use plugger_core::Pluggable;
+use plugger_core::{Pluggable, PluggableValue};
+use std::any::TypeId;
+use std::mem;
+use std::sync::Mutex;
 
 static mut VM_INITIALISED: bool = false;
 
+/// The Ruby classes that have been defined, by Rust type.
+static REGISTERED_CLASSES: Mutex<Vec<(TypeId, ffi::VALUE)>> = Mutex::new(Vec::new());
+/// The Ruby classes of data-carrying enums, by Rust type path.
+static REGISTERED_ENUMS: Mutex<Vec<(&'static str, ffi::VALUE)>> = Mutex::new(Vec::new());
+
 pub extern fn do_something() {
     println!("do_something");
 }
@@ -30,44 +59,163 @@ pub extern fn do_something() {
 pub enum ErrorKind
 {
     Ruby(rurust::ErrorKind),
+    /// A Ruby value could not be converted into a Rust value.
+    Marshall(plugger_core::MarshallError),
+    /// A class or module could not be defined because its name is taken.
+    NameCollision {
+        /// The fully qualified Ruby name.
+        name: String,
+        /// The Rust path of the plugged class or module that has the name,
+        /// or `None` if it was defined by Ruby code.
+        defined_by: Option<String>,
+    },
+    /// A name given to a `Binding` is not a valid name for it, or a class,
+    /// module or constant would be given a name Ruby doesn't allow.
+    InvalidName(String),
+}
+
+/// How a plugged object can be reached from Ruby code.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Binding<'a>
+{
+    /// A global constant, named in upper case, like `PLAYER`.
+    ///
+    /// Plugging another object with the same name replaces it.
+    Constant(&'a str),
+    /// A global variable, like `$player`.
+    Global(&'a str),
+    /// A local variable of the scripts run by `Ruby::eval_script`.
+    ///
+    /// The name must be a valid name for a local variable, otherwise plugging
+    /// fails with `ErrorKind::InvalidName`.
+    Local(&'a str),
+    /// Not bound to any name. The object is only returned, and so must be
+    /// kept reachable from Ruby to stop it from being garbage collected.
+    Unbound,
 }
 
-pub struct Ruby;
+/// A function called with a description of everything that is plugged in.
+type Logger = Box<dyn Fn(&str) + Send>;
+
+pub struct Ruby
+{
+    /// Called with a description of everything that is plugged in.
+    ///
+    /// Must be `Send`, so that the VM can be handed to another thread.
+    logger: Option<Logger>,
+}
 
 impl Ruby
 {
     pub fn new() -> Result<Self, ErrorKind> {
-        Ok(Ruby)
+        Ok(Ruby { logger: None })
     }
 
-    pub fn plug<P>(&mut self, name: &str, object: &mut P) where P: Pluggable {
+    /// Sets a function to call with a message whenever something is plugged in.
+    pub fn set_logger<F>(&mut self, logger: F) where F: Fn(&str) + Send + 'static {
+        self.logger = Some(Box::new(logger));
+    }
+
+    /// Defines the Ruby class of a pluggable type, if it hasn't been already.
+    ///
+    /// Static methods can be called as soon as the class is registered, and
+    /// plugging objects of a registered type doesn't define the class again.
+    ///
+    /// The class is nested in modules named after its Rust module path, so
+    /// `game::world::Player` becomes `World::Player`, unless it was given a
+    /// namespace with `#[pluggable(namespace = "...")]`. Fails if the name
+    /// is already taken, which is the case for types at the same path in two
+    /// crates.
+    pub fn register_class<P>(&mut self) -> Result<Value, ErrorKind> where P: Pluggable {
+        // Classes are only defined while holding the VM, so two threads can't
+        // both define the same one.
         let mut vm = vm();
-        let base_class = vm.eval(PLUGGER_BASE_CLASS).expect("could not find the plugger base class");
 
-        let class_builder = object.methods().iter().fold(vm.class(object.name()).extend(base_class), |class, method| {
-            let ptr = method.marshall("ruby") as usize;
-            let ptr_value = vm.eval(&format!("{}", ptr)).unwrap();
-            let realised_param_count = method.parameters.len();
-            let actual_param_count = realised_param_count + 1; // Account for the hidden func ptr parameter.
+        if let Some(class) = registered_class(TypeId::of::<P>()) {
+            return Ok(class);
+        }
 
-            let name = format!("{}_internal", method.name);
+        let class = define_class(&mut vm, &P::class())?;
+        REGISTERED_CLASSES.lock().unwrap().push((TypeId::of::<P>(), class.0));
+        Ok(class)
+    }
 
-            if method.is_static {
-                class.singleton_method(name, shims::ruby_function(realised_param_count) as *mut _, actual_param_count as i8)
-            } else {
-                class.method(name, shims::ruby_method(realised_param_count) as *mut _, actual_param_count as i8)
-            }.constant(method.name.to_uppercase(), ptr_value)
-        });
+    /// Defines the Ruby class of a data-carrying enum, if it hasn't been
+    /// already.
+    ///
+    /// The class is otherwise defined the first time Rust gives a variant to
+    /// Ruby, so this lets scripts create variants, such as
+    /// `Command.new(:jump, height: 2)`, before then. It is named the same way
+    /// as the class of a struct. Fails if the name is already taken.
+    pub fn register_enum<E>(&mut self) -> Result<Value, ErrorKind> where E: PluggableValue {
+        let _vm = vm();
+        enum_class::<E>()
+    }
+
+    /// Plugs in an object as a global constant, such as `PLAYER`.
+    pub fn plug<P>(&mut self, name: &str, object: &mut P) -> Result<Value, ErrorKind> where P: Pluggable {
+        self.plug_as(Binding::Constant(name), object)
+    }
 
-        class_builder.build();
-        let ptr = object as *mut _ as usize;
+    /// Plugs in an object, giving the Ruby object that wraps it.
+    pub fn plug_as<P>(&mut self, binding: Binding, object: &mut P) -> Result<Value, ErrorKind> where P: Pluggable {
+        let class = self.register_class::<P>()?;
+        let ruby_val = data::wrap_object(class, object);
 
-        let constant_name = name.to_uppercase();
+        match bind(&mut vm(), binding, ruby_val)? {
+            Some(name) => self.log(&format!("plugged in {} as {}", P::type_name(), name)),
+            None => self.log(&format!("plugged in {}", P::type_name())),
+        }
+        Ok(ruby_val)
+    }
+
+    /// Removes whatever was plugged in with a binding, so that Ruby no longer
+    /// keeps it reachable.
+    ///
+    /// Constants are only removed if they hold a plugged object, and globals
+    /// are set to `nil`. Does nothing if nothing was bound.
+    pub fn unbind(&mut self, binding: Binding) -> Result<(), ErrorKind> {
+        let mut vm = vm();
 
-        let ruby_val = vm.eval(&format!("{}.new({})", object.name(), ptr)).unwrap();
-        vm.set_global_const(&constant_name, ruby_val);
+        match binding {
+            Binding::Constant(name) => {
+                let name = name.to_uppercase();
+                let base_class = vm.eval(PLUGGER_BASE_CLASS).expect("could not find the plugger base class");
 
-        println!("Plugging in {} {}", object.name(), constant_name);
+                if let Some(existing) = constant(None, &name) {
+                    if existing.send("is_a?", &[base_class]).is_true() {
+                        rurust::classes::Object().send("send", &[Value::symbol("remove_const"), Value::symbol(&name)]);
+                    }
+                }
+            },
+            Binding::Global(name) => {
+                let name = global_name(name);
+                vm.set_global(&name, Value::nil());
+            },
+            Binding::Local(name) => {
+                let script_module = vm.eval(PLUGGER_SCRIPT_MODULE).expect("could not find the plugger script module");
+                script_module.get_ivar("@locals").send("delete", &[Value::symbol(name)]);
+            },
+            Binding::Unbound => (),
+        }
+        Ok(())
+    }
+
+    /// Defines the functions of a pluggable module on a Ruby module.
+    ///
+    /// The Ruby module is named after the Rust module path without the crate
+    /// name, so `game::world` becomes `World`, unless it was given a namespace
+    /// with `#[pluggable(namespace = "...")]`. Functions at the root of a
+    /// crate are put into a module named after the crate.
+    ///
+    /// Plugging more functions from the same Rust module adds to it. Fails if
+    /// the name is taken by anything else.
+    pub fn plug_module(&mut self, module: &plugger_core::Module) -> Result<Value, ErrorKind> {
+        let mut vm = vm();
+        let ruby_module = define_module(&mut vm, module)?;
+
+        self.log(&format!("plugged in module {} as {}", module.path, naming::module_names(module).join("::")));
+        Ok(ruby_module)
     }
 
     pub fn eval(&mut self, code: &str) -> Result<Value, ErrorKind> {
@@ -77,6 +225,342 @@ impl Ruby
         }
     }
 
+    /// Evaluates a script, with every object plugged in as a `Binding::Local`
+    /// in scope.
+    ///
+    /// Local variables defined by the script don't outlive it.
+    pub fn eval_script(&mut self, code: &str) -> Result<Value, ErrorKind> {
+        {
+            let mut vm = vm();
+            let script_module = vm.eval(PLUGGER_SCRIPT_MODULE).expect("could not find the plugger script module");
+            script_module.set_ivar("@code", ruby_string(code));
+        }
+
+        self.eval(&format!("{}.run", PLUGGER_SCRIPT_MODULE))
+    }
+
+    /// Evaluates some code, converting the result into a language-neutral value.
+    pub fn eval_value(&mut self, code: &str) -> Result<plugger_core::Value, ErrorKind> {
+        let value = self.eval(code)?;
+        <Marshall as plugger_core::Marshall>::to_dynamic(value).map_err(ErrorKind::Marshall)
+    }
+
+    fn log(&self, message: &str) {
+        if let Some(ref logger) = self.logger {
+            logger(message);
+        }
+    }
+}
+
+/// Gets the Ruby class that a Rust type was registered as.
+fn registered_class(type_id: TypeId) -> Option<Value> {
+    let registered_classes = REGISTERED_CLASSES.lock().unwrap();
+
+    registered_classes.iter().find(|&&(registered, _)| registered == type_id).map(|&(_, class)| Value::from(class))
+}
+
+/// Gets the Ruby class of a data-carrying enum, defining it the first time.
+///
+/// Must only be called while holding the VM, or from Ruby code, so that two
+/// threads can't both define the same class.
+fn enum_class<E>() -> Result<Value, ErrorKind> where E: PluggableValue {
+    if let Some(class) = registered_enum(E::type_path()) {
+        return Ok(class);
+    }
+
+    let names = naming::enum_class_names::<E>();
+    check_constant_names(&names)?;
+    let (name, parent_names) = names.split_last().unwrap();
+    let parent = define_namespace(parent_names)?;
+    if let Some(existing) = constant(parent, name) {
+        return Err(name_collision(&names, existing));
+    }
+
+    let base_class = rurust::classes::Object().send("const_get", &[Value::symbol(PLUGGER_ENUM_CLASS)]);
+    let class = rurust::builder::Class::new_under(&name[..], parent).extend(base_class).build();
+    unsafe { ffi::rb_gc_register_mark_object(class.0) };
+    class.set_ivar("@rust_path", ruby_string(E::type_path()));
+
+    REGISTERED_ENUMS.lock().unwrap().push((E::type_path(), class.0));
+    Ok(class)
+}
+
+/// Gets the Ruby class that a data-carrying enum was defined as.
+fn registered_enum(type_path: &str) -> Option<Value> {
+    let registered_enums = REGISTERED_ENUMS.lock().unwrap();
+
+    registered_enums.iter().find(|&&(registered, _)| registered == type_path).map(|&(_, class)| Value::from(class))
+}
+
+/// Makes a plugged object reachable from Ruby code, giving the name it can
+/// be reached by.
+fn bind(vm: &mut rurust::VM, binding: Binding, object: Value) -> Result<Option<String>, ErrorKind> {
+    match binding {
+        Binding::Constant(name) => {
+            let name = name.to_uppercase();
+
+            // Redefining a constant makes Ruby warn, so a plugged object is
+            // removed first. Anything else is left alone.
+            if let Some(existing) = constant(None, &name) {
+                let base_class = vm.eval(PLUGGER_BASE_CLASS).expect("could not find the plugger base class");
+
+                if !existing.send("is_a?", &[base_class]).is_true() {
+                    return Err(ErrorKind::NameCollision { name, defined_by: None });
+                }
+                rurust::classes::Object().send("send", &[Value::symbol("remove_const"), Value::symbol(&name)]);
+            }
+
+            vm.set_global_const(&name, object);
+            Ok(Some(name))
+        },
+        Binding::Global(name) => {
+            let name = global_name(name);
+
+            vm.set_global(&name, object);
+            Ok(Some(name))
+        },
+        Binding::Local(name) => {
+            if !is_local_name(name) {
+                return Err(ErrorKind::InvalidName(name.to_owned()));
+            }
+            let script_module = vm.eval(PLUGGER_SCRIPT_MODULE).expect("could not find the plugger script module");
+
+            script_module.get_ivar("@locals").send("[]=", &[Value::symbol(name), object]);
+            Ok(Some(name.to_owned()))
+        },
+        Binding::Unbound => Ok(None),
+    }
+}
+
+/// Gets the name of a global variable, with the leading `$`.
+fn global_name(name: &str) -> String {
+    if name.starts_with('$') { name.to_owned() } else { format!("${}", name) }
+}
+
+/// Checks if a name can be used for a Ruby local variable.
+fn is_local_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    let starts_like_local = matches!(chars.next(), Some(c) if c.is_lowercase() || c == '_');
+
+    starts_like_local && chars.all(|c| c.is_alphanumeric() || c == '_') && !RUBY_KEYWORDS.contains(&name)
+}
+
+/// Checks that every name can be used for a Ruby constant, as Ruby raises
+/// when asked about one that can't.
+fn check_constant_names<S>(names: &[S]) -> Result<(), ErrorKind> where S: AsRef<str> {
+    for name in names.iter().map(AsRef::as_ref) {
+        let mut chars = name.chars();
+        let starts_like_constant = matches!(chars.next(), Some(c) if c.is_uppercase());
+
+        if !starts_like_constant || !chars.all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(ErrorKind::InvalidName(name.to_owned()));
+        }
+    }
+    Ok(())
+}
+
+/// Defines the Ruby class of a pluggable type.
+///
+/// An exception would unwind past the VM and everything owned here, so the
+/// names are checked before Ruby sees them, and only the getters of
+/// constants are left to raise, inside `error::protect`.
+fn define_class(vm: &mut rurust::VM, class: &plugger_core::Class) -> Result<Value, ErrorKind> {
+    let base_class = vm.eval(PLUGGER_BASE_CLASS).expect("could not find the plugger base class");
+
+    // The class is only ever defined once, so the name must be free.
+    let names = naming::class_names(class);
+    check_constant_names(&names)?;
+    check_constant_names(&class.constants.iter().map(|constant| constant.name).collect::<Vec<_>>())?;
+    let (name, parent_names) = names.split_last().unwrap();
+    let parent = define_namespace(parent_names)?;
+    if let Some(existing) = constant(parent, name) {
+        return Err(name_collision(&names, existing));
+    }
+
+    // Every method is defined directly, so Ruby checks the number of
+    // arguments and calls straight into the shim.
+    let class_builder = rurust::builder::Class::new_under(&name[..], parent).extend(base_class);
+    let class_builder = class.methods.iter().fold(class_builder, |builder, method| {
+        let param_count = method.parameters.len();
+
+        if method.is_static {
+            builder.singleton_method(method.name, shims::ruby_function(param_count) as *mut _, param_count as i8)
+        } else {
+            builder.method(method.name, shims::ruby_method(param_count) as *mut _, param_count as i8)
+        }
+    });
+
+    let ruby_class = class_builder.build();
+
+    // The class is only referenced from Rust once it has been registered,
+    // so scripts removing the constant mustn't let it be collected.
+    unsafe { ffi::rb_gc_register_mark_object(ruby_class.0) };
+
+    // The shims look the functions up by class and method name. They are
+    // kept on the Rust side, where scripts can't swap them around.
+    for method in class.methods.iter() {
+        data::register_function(ruby_class, method.name, data::Function {
+            pointer: method.marshall("ruby"),
+            arg_count: method.parameters.len(),
+            is_static: method.is_static,
+            receiver: Some((class.type_id, class.name.clone())),
+        });
+    }
+
+    let docs = vm.eval("{}").unwrap();
+    for method in class.methods.iter() {
+        docs.send("[]=", &[Value::symbol(method.name), ruby_string(method.docs)]);
+    }
+    define_constants(ruby_class, &class.constants, docs)?;
+    ruby_class.set_ivar("@rust_docs", docs.call_no_args("freeze"));
+    ruby_class.set_ivar("@rust_class_docs", ruby_string(class.docs));
+    ruby_class.set_ivar("@rust_path", ruby_string(&class.path));
+    ruby_class.set_ivar("@rust_methods", ruby_description(reflection::methods(&class.methods)));
+    ruby_class.set_ivar("@rust_fields", ruby_description(reflection::fields(&class.fields)));
+
+    Ok(ruby_class)
+}
+
+/// Defines a Ruby module containing the functions of a pluggable module.
+///
+/// Like `define_class`, nothing here raises other than constant getters.
+fn define_module(vm: &mut rurust::VM, module: &plugger_core::Module) -> Result<Value, ErrorKind> {
+    let reflection = vm.eval(PLUGGER_REFLECTION_MODULE).expect("could not find the plugger reflection module");
+
+    // The module can only be reopened if it holds functions from the same
+    // Rust module.
+    let names = naming::module_names(module);
+    check_constant_names(&names)?;
+    check_constant_names(&module.constants.iter().map(|constant| constant.name).collect::<Vec<_>>())?;
+    let (name, parent_names) = names.split_last().unwrap();
+    let parent = define_namespace(parent_names)?;
+    if let Some(existing) = constant(parent, name) {
+        if rust_path(existing).as_ref() != Some(&module.path) {
+            return Err(name_collision(&names, existing));
+        }
+    }
+
+    let module_builder = module.functions.iter().fold(rurust::builder::Module::new_under(&name[..], parent), |builder, function| {
+        let param_count = function.parameters.len();
+        builder.function(function.name, shims::ruby_function(param_count) as *mut _, param_count as i8)
+    });
+
+    let ruby_module = module_builder.build();
+    unsafe { ffi::rb_gc_register_mark_object(ruby_module.0) };
+
+    // The module may already hold the functions of another Rust module, so
+    // the descriptions are merged rather than replaced.
+    let docs = vm.eval("{}").unwrap();
+    for function in module.functions.iter() {
+        data::register_function(ruby_module, function.name, data::Function {
+            pointer: function.marshall("ruby"),
+            arg_count: function.parameters.len(),
+            is_static: true,
+            receiver: None,
+        });
+
+        docs.send("[]=", &[Value::symbol(function.name), ruby_string(function.docs)]);
+    }
+    define_constants(ruby_module, &module.constants, docs)?;
+    merge_ivar(ruby_module, "@rust_docs", "merge", docs);
+    merge_ivar(ruby_module, "@rust_methods", "|", ruby_description(reflection::methods(&module.functions)));
+
+    if !module.docs.is_empty() {
+        ruby_module.set_ivar("@rust_class_docs", ruby_string(module.docs));
+    }
+    ruby_module.set_ivar("@rust_path", ruby_string(&module.path));
+
+    ruby_module.send("extend", &[reflection]);
+    Ok(ruby_module)
+}
+
+/// Defines constants on a class or module, adding their docs to `docs`.
+///
+/// Constants that are already defined are left alone, so that plugging a
+/// module again doesn't make Ruby warn about them.
+///
+/// A getter raises if its value can't be marshalled. It owns nothing, so it
+/// is the only thing called through `error::protect`.
+fn define_constants(ruby_module: Value, constants: &[plugger_core::Constant], docs: Value) -> Result<(), ErrorKind> {
+    for constant in constants.iter() {
+        let name = Value::symbol(constant.name);
+        docs.send("[]=", &[name, ruby_string(constant.docs)]);
+
+        if ruby_module.send("const_defined?", &[name, Value::boolean(false)]).is_true() {
+            continue;
+        }
+
+        let getter: fn() -> Value = unsafe { mem::transmute(constant.marshall("ruby")) };
+        let value = error::protect(getter).map_err(ErrorKind::Ruby)?;
+        ruby_module.send("const_set", &[name, value.call_no_args("freeze")]);
+    }
+    Ok(())
+}
+
+/// Defines the nested modules of a namespace, giving the innermost one.
+///
+/// Modules that already exist are reopened, but the names must not be taken
+/// by anything other than a module.
+fn define_namespace(names: &[String]) -> Result<Option<Value>, ErrorKind> {
+    let mut parent = None;
+
+    for (index, name) in names.iter().enumerate() {
+        if let Some(existing) = constant(parent, name) {
+            if !existing.is_module() {
+                return Err(name_collision(&names[..index + 1], existing));
+            }
+        }
+        parent = Some(rurust::builder::Module::new_under(&name[..], parent).build());
+    }
+    Ok(parent)
+}
+
+/// Gets a constant defined directly in a module, or at the top level.
+fn constant(parent: Option<Value>, name: &str) -> Option<Value> {
+    let parent = parent.unwrap_or_else(rurust::classes::Object);
+    let name = Value::symbol(name);
+
+    if parent.send("const_defined?", &[name, Value::boolean(false)]).is_true() {
+        Some(parent.send("const_get", &[name, Value::boolean(false)]))
+    } else {
+        None
+    }
+}
+
+/// Gets the Rust path of a plugged class or module.
+fn rust_path(value: Value) -> Option<String> {
+    if value.is_class() || value.is_module() {
+        value.get_ivar("@rust_path").as_string()
+    } else {
+        None
+    }
+}
+
+fn name_collision(names: &[String], existing: Value) -> ErrorKind {
+    ErrorKind::NameCollision {
+        name: names.join("::"),
+        defined_by: rust_path(existing),
+    }
+}
+
+/// Combines a frozen description in an instance variable with new entries.
+fn merge_ivar(object: Value, name: &str, operator: &str, entries: Value) {
+    let existing = object.get_ivar(name);
+    let merged = if existing.is_nil() { entries } else { existing.send(operator, &[entries]) };
+
+    object.set_ivar(name, merged.call_no_args("freeze"));
+}
+
+/// Creates a frozen Ruby string.
+fn ruby_string(s: &str) -> Value {
+    <Marshall as plugger_core::Marshall>::from_string(s.to_owned()).call_no_args("freeze")
+}
+
+/// Converts a description from the `reflection` module into a frozen Ruby value.
+fn ruby_description(description: plugger_core::Value) -> Value {
+    <Marshall as plugger_core::Marshall>::from_dynamic(description)
+        .expect("descriptions only contain plain values")
+        .call_no_args("freeze")
 }
 
 fn vm() -> ::std::sync::MutexGuard<'static, rurust::VM> {
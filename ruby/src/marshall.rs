@@ -1,87 +1,365 @@
-use plugger_core;
-use rurust::Value;
+use plugger_core::{self, MarshallError, PluggableValue, Variant};
+use plugger_core::Value as Dynamic;
+use {data, error, ffi};
+use rurust::{classes, Value};
+
+use libc;
+use std::convert::{TryFrom, TryInto};
+use std::slice;
+use std::str::FromStr;
 
 pub struct Marshall;
 
-macro_rules! to_uint {
-    ($ty:ident, $value:expr) => {
-        {
-            let v = $value.to_u64();
+impl plugger_core::Marshall for Marshall {
+    type Value = Value;
+
+    fn nil() -> Value { Value::nil() }
+    fn is_nil(value: &Value) -> bool { value.is_nil() }
 
-            if v > ($ty::max_value() as u64) {
-                panic!("out of bounds");
-            }
-            v as $ty
+    fn is_truthy(value: &Value) -> bool {
+        !value.is_nil() && !value.is_false()
+    }
+
+    fn to_bool(value: Value) -> Result<bool, MarshallError> {
+        if value.is_true() {
+            Ok(true)
+        } else if value.is_false() {
+            Ok(false)
+        } else {
+            Err(type_mismatch("true or false", &value))
         }
     }
-}
 
-macro_rules! to_int {
-    ($ty:ident, $value:expr) => {
-        {
-            let v = $value.to_i64();
+    fn to_u8(value: Value) -> Result<u8, MarshallError> { to_integer("u8", value) }
+    fn to_u16(value: Value) -> Result<u16, MarshallError> { to_integer("u16", value) }
+    fn to_u32(value: Value) -> Result<u32, MarshallError> { to_integer("u32", value) }
+    fn to_u64(value: Value) -> Result<u64, MarshallError> { to_integer("u64", value) }
+    fn to_i8(value: Value) -> Result<i8, MarshallError> { to_integer("i8", value) }
+    fn to_i16(value: Value) -> Result<i16, MarshallError> { to_integer("i16", value) }
+    fn to_i32(value: Value) -> Result<i32, MarshallError> { to_integer("i32", value) }
+    fn to_i64(value: Value) -> Result<i64, MarshallError> { to_integer("i64", value) }
+    fn to_u128(value: Value) -> Result<u128, MarshallError> { to_integer("u128", value) }
+    fn to_i128(value: Value) -> Result<i128, MarshallError> { to_integer("i128", value) }
+    fn to_usize(value: Value) -> Result<usize, MarshallError> { to_integer("usize", value) }
+    fn to_isize(value: Value) -> Result<isize, MarshallError> { to_integer("isize", value) }
+
+    fn to_f32(value: Value) -> Result<f32, MarshallError> {
+        Ok(value.to_f64() as f32)
+    }
+
+    fn to_f64(value: Value) -> Result<f64, MarshallError> {
+        Ok(value.to_f64())
+    }
+
+    fn to_char(value: Value) -> Result<char, MarshallError> {
+        let s = Self::to_string(value)?;
+        let mut chars = s.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(MarshallError::TypeMismatch {
+                expected: "a single character".to_owned(),
+                got: format!("{} characters", s.chars().count()),
+            }),
+        }
+    }
+
+    fn to_string(value: Value) -> Result<String, MarshallError> {
+        if !value.is_string() {
+            return Err(type_mismatch("String", &value));
+        }
+
+        let encoding = value.call_no_args("encoding").display_string();
+        if !value.call_no_args("valid_encoding?").is_true() {
+            return Err(MarshallError::InvalidEncoding { encoding });
+        }
 
-            if v > ($ty::max_value() as i64) ||
-                v < ($ty::min_value() as i64) {
-                panic!("out of bounds");
+        // Binary strings are assumed to hold UTF-8, and everything else is
+        // transcoded by Ruby, which raises if there is no way to do so.
+        let value = if UTF8_COMPATIBLE_ENCODINGS.contains(&&encoding[..]) {
+            value
+        } else {
+            match error::protect(|| value.send("encode", &[Value::string("UTF-8")])) {
+                Ok(value) => value,
+                Err(..) => return Err(MarshallError::InvalidEncoding { encoding }),
             }
-            v as $ty
+        };
+
+        String::from_utf8(Self::to_bytes(value)?).map_err(|_| {
+            MarshallError::InvalidEncoding { encoding }
+        })
+    }
+
+    fn from_bool(value: bool) -> Value { Value::boolean(value) }
+    fn from_u8(value: u8) -> Value { from_integer(value) }
+    fn from_u16(value: u16) -> Value { from_integer(value) }
+    fn from_u32(value: u32) -> Value { from_integer(value) }
+    fn from_u64(value: u64) -> Value { from_integer(value) }
+    fn from_i8(value: i8) -> Value { from_integer(value) }
+    fn from_i16(value: i16) -> Value { from_integer(value) }
+    fn from_i32(value: i32) -> Value { from_integer(value) }
+    fn from_i64(value: i64) -> Value { from_integer(value) }
+    fn from_u128(value: u128) -> Value { from_integer(value) }
+    fn from_i128(value: i128) -> Value { from_integer(value) }
+    fn from_usize(value: usize) -> Value { from_integer(value) }
+    fn from_isize(value: isize) -> Value { from_integer(value) }
+    fn from_f32(value: f32) -> Value { Value::float(value as f64) }
+    fn from_f64(value: f64) -> Value { Value::float(value) }
+    fn from_char(value: char) -> Value { Self::from_string(value.to_string()) }
+    fn from_string(value: String) -> Value { Self::from_str(&value) }
+
+    fn from_str(value: &str) -> Value {
+        Value::from(unsafe {
+            ffi::rb_utf8_str_new(value.as_ptr() as *const libc::c_char, value.len() as libc::c_long)
+        })
+    }
+
+    fn to_bytes(value: Value) -> Result<Vec<u8>, MarshallError> {
+        if !value.is_string() {
+            return Err(type_mismatch("String", &value));
         }
+
+        // The bytes are copied as-is, whatever the string's encoding.
+        let length = value.call_no_args("bytesize").to_u64() as usize;
+        let mut raw = value.0;
+        let ptr = unsafe { ffi::rb_string_value_ptr(&mut raw) };
+
+        Ok(unsafe { slice::from_raw_parts(ptr as *const u8, length) }.to_vec())
     }
-}
 
-impl plugger_core::Marshall for Marshall {
-    type Value = Value;
+    fn from_bytes(bytes: &[u8]) -> Value {
+        Value::from(unsafe {
+            ffi::rb_str_new(bytes.as_ptr() as *const libc::c_char, bytes.len() as libc::c_long)
+        })
+    }
+
+    fn to_array(value: Value) -> Result<Vec<Value>, MarshallError> {
+        if !value.is_array() {
+            return Err(type_mismatch("Array", &value));
+        }
+
+        let length = value.call_no_args("length").to_u64();
+        Ok((0..length).map(|index| value.send("[]", &[Value::integer(index as i64)])).collect())
+    }
+
+    fn from_array(values: Vec<Value>) -> Value {
+        let array = classes::Array().call_no_args("new");
 
-    fn to_bool(value: Value) -> bool {
-        // TODO: we might want to do a truthy check.
-        value.is_true()
+        for value in values {
+            array.send("push", &[value]);
+        }
+        array
     }
 
-    fn to_u8(value: Value) -> u8 { to_uint!(u8, value) }
-    fn to_u16(value: Value) -> u16 { to_uint!(u16, value) }
-    fn to_u32(value: Value) -> u32 { to_uint!(u32, value) }
-    fn to_u64(value: Value) -> u64 { value.to_u64() }
-    fn to_i8(value: Value) -> i8 { to_int!(i8, value) }
-    fn to_i16(value: Value) -> i16 { to_int!(i16, value) }
-    fn to_i32(value: Value) -> i32 { to_int!(i32, value) }
-    fn to_i64(value: Value) -> i64 { value.to_i64() }
+    fn to_map(value: Value) -> Result<Vec<(Value, Value)>, MarshallError> {
+        if !value.is_hash() {
+            return Err(type_mismatch("Hash", &value));
+        }
 
-    fn to_f32(value: Value) -> f32 {
-        value.to_f64() as f32
+        Ok(Self::to_array(value.call_no_args("to_a"))?.into_iter().map(|pair| {
+            (pair.send("[]", &[Value::integer(0)]), pair.send("[]", &[Value::integer(1)]))
+        }).collect())
     }
 
-    fn to_f64(value: Value) -> f64 {
-        value.to_f64()
+    fn from_map(pairs: Vec<(Value, Value)>) -> Value {
+        let hash = classes::Hash().call_no_args("new");
+
+        for (key, value) in pairs {
+            hash.send("[]=", &[key, value]);
+        }
+        hash
     }
 
-    fn to_string(value: Value) -> String {
-        if let Some(s) = value.as_string() {
-            s
+    fn to_symbol(value: Value) -> Result<String, MarshallError> {
+        if value.is_symbol() {
+            Self::to_string(value.call_no_args("to_s"))
+        } else if value.is_string() {
+            Self::to_string(value)
         } else {
-            panic!("Ruby value is not a String");
+            Err(type_mismatch("Symbol", &value))
         }
     }
 
-    fn from_bool(value: bool) -> Value { Value::boolean(value) }
-    fn from_u8(value: u8) -> Value { Value::integer(value as i64) }
-    fn from_u16(value: u16) -> Value { Value::integer(value as i64) }
-    fn from_u32(value: u32) -> Value { Value::integer(value as i64) }
-    fn from_u64(value: u64) -> Value { Value::integer(value as i64) } // FIXME: this may overflow
-    fn from_i8(value: i8) -> Value { Value::integer(value as i64) }
-    fn from_i16(value: i16) -> Value { Value::integer(value as i64) }
-    fn from_i32(value: i32) -> Value { Value::integer(value as i64) }
-    fn from_i64(value: i64) -> Value { Value::integer(value) }
-    fn from_f32(value: f32) -> Value { Value::float(value as f64) }
-    fn from_f64(value: f64) -> Value { Value::float(value) }
-    fn from_string(value: String) -> Value {
-        Value::string(value)
+    fn from_symbol(name: String) -> Value {
+        Value::symbol(name)
+    }
+
+    fn to_variant<T>(value: Value) -> Result<Variant<Value>, MarshallError>
+        where T: PluggableValue {
+        // A variant without fields may be given as a plain symbol.
+        if value.is_symbol() {
+            return Ok(Variant { name: Self::to_symbol(value)?, fields: Vec::new() });
+        }
+
+        // Until the class is defined, no value can be one of its variants.
+        let class = ::registered_enum(T::type_path());
+        if class != Some(Value::from(unsafe { ffi::rb_obj_class(value.0) })) {
+            return Err(type_mismatch(T::type_name(), &value));
+        }
+
+        let name = Self::to_symbol(value.call_no_args("variant"))?;
+        let fields = Self::to_map(value.call_no_args("fields"))?.into_iter().map(|(name, value)| {
+            Ok((Self::to_symbol(name)?, value))
+        }).collect::<Result<_, _>>()?;
+
+        Ok(Variant { name, fields })
+    }
+
+    fn from_variant<T>(variant: Variant<Value>) -> Value
+        where T: PluggableValue {
+        // Raising skips destructors, so the variant is converted first.
+        let Variant { name, fields } = variant;
+        let name = Value::symbol(name);
+        let fields = Self::from_map(fields.into_iter().map(|(name, value)| {
+            (Value::symbol(name), value)
+        }).collect());
+
+        let class = match ::enum_class::<T>() {
+            Ok(class) => class,
+            Err(error) => {
+                let message = format!("could not define the class of {}: {:?}", T::type_path(), error);
+                drop(error);
+                error::raise(classes::NameError(), message)
+            },
+        };
+
+        class.send("new", &[name, fields])
+    }
+
+    fn to_dynamic(value: Value) -> Result<Dynamic, MarshallError> {
+        if value.is_nil() {
+            Ok(Dynamic::Nil)
+        } else if value.is_true() || value.is_false() {
+            Ok(Dynamic::Bool(value.is_true()))
+        } else if value.send("is_a?", &[classes::Integer()]).is_true() {
+            // Only integers above `i128::MAX` need a `UInt`.
+            match Self::to_i128(value) {
+                Ok(i) => Ok(Dynamic::Int(i)),
+                Err(..) => Self::to_u128(value).map(Dynamic::UInt),
+            }
+        } else if value.is_float() {
+            Ok(Dynamic::Float(value.to_f64()))
+        } else if value.is_symbol() {
+            Ok(Dynamic::Symbol(Self::to_symbol(value)?))
+        } else if value.is_string() && value.call_no_args("encoding").display_string() == "ASCII-8BIT" {
+            Ok(Dynamic::Bytes(Self::to_bytes(value)?))
+        } else if value.is_string() {
+            Ok(Dynamic::String(Self::to_string(value)?))
+        } else if value.is_array() {
+            Ok(Dynamic::Array(plugger_core::marshall_elements(Self::to_array(value)?, Self::to_dynamic)?))
+        } else if value.is_hash() {
+            Ok(Dynamic::Map(plugger_core::marshall_elements(Self::to_map(value)?, |(key, value)| {
+                Ok((Self::to_dynamic(key)?, Self::to_dynamic(value)?))
+            })?))
+        } else if let Some(handle) = data::object_handle(value) {
+            Ok(Dynamic::Object(handle))
+        } else {
+            Err(type_mismatch("a plain value or a Rust object", &value))
+        }
+    }
+
+    fn from_dynamic(value: Dynamic) -> Result<Value, MarshallError> {
+        Ok(match value {
+            Dynamic::Nil => Value::nil(),
+            Dynamic::Bool(b) => Value::boolean(b),
+            Dynamic::Int(i) => from_integer(i),
+            Dynamic::UInt(u) => from_integer(u),
+            Dynamic::Float(f) => Value::float(f),
+            Dynamic::String(s) => Self::from_str(&s),
+            Dynamic::Symbol(s) => Value::symbol(s),
+            Dynamic::Bytes(bytes) => Self::from_bytes(&bytes),
+            Dynamic::Array(values) => {
+                Self::from_array(plugger_core::marshall_elements(values, Self::from_dynamic)?)
+            },
+            Dynamic::Map(pairs) => {
+                Self::from_map(plugger_core::marshall_elements(pairs, |(key, value)| {
+                    Ok((Self::from_dynamic(key)?, Self::from_dynamic(value)?))
+                })?)
+            },
+            Dynamic::Object(handle) => {
+                // Classes are namespaced, so they are found by type rather
+                // than by name.
+                let class = ::registered_class(handle.type_id()).ok_or_else(|| MarshallError::TypeMismatch {
+                    expected: "a registered class".to_owned(),
+                    got: handle.type_name().to_owned(),
+                })?;
+
+                data::wrap_handle(class, handle)
+            },
+        })
     }
 
-    fn object_pointer_from_value<T>(value: Value) -> *mut T
+    fn object_pointer_from_value<T>(value: Value) -> Result<*mut T, MarshallError>
         where T: plugger_core::Pluggable {
-        let pointer_val = value.call_no_args("object_pointer");
-        pointer_val.to_u64() as *mut T
+        data::unwrap_object_of::<T>(value)
     }
 }
 
+impl plugger_core::Raise for Marshall {
+    fn raise(error: MarshallError) -> ! {
+        let class = match *error.innermost() {
+            MarshallError::TypeMismatch { .. } => classes::TypeError(),
+            MarshallError::OutOfRange { .. } => classes::RangeError(),
+            MarshallError::InvalidEncoding { .. } => classes::ArgError(),
+            MarshallError::LengthMismatch { .. } => classes::ArgError(),
+            MarshallError::Element { .. } => unreachable!(),
+            MarshallError::UnknownVariant { .. } => classes::ArgError(),
+            MarshallError::MissingField(..) => classes::ArgError(),
+            MarshallError::Returned(..) => classes::RuntimeError(),
+            MarshallError::Serialization(..) => classes::TypeError(),
+        };
+
+        let message = error.to_string();
+        drop(error);
+        error::raise(class, message)
+    }
+}
+
+/// Encodings whose bytes can be read as UTF-8 without transcoding.
+const UTF8_COMPATIBLE_ENCODINGS: &[&str] = &["UTF-8", "US-ASCII", "ASCII-8BIT"];
+
+/// The largest integer that fits in a Ruby `Fixnum`.
+const FIXNUM_MAX: i64 = isize::MAX as i64 >> 1;
+/// The smallest integer that fits in a Ruby `Fixnum`.
+const FIXNUM_MIN: i64 = isize::MIN as i64 >> 1;
+
+/// Converts a Ruby `Integer` into a Rust integer, checking that it fits.
+fn to_integer<T>(ty: &str, value: Value) -> Result<T, MarshallError>
+    where T: TryFrom<i64> + FromStr {
+    if value.is_integer() {
+        // A `Fixnum` always fits in an `i64`.
+        let v = value.to_i64();
+        T::try_from(v).map_err(|_| out_of_range(ty, v.to_string()))
+    } else if value.send("is_a?", &[classes::Integer()]).is_true() {
+        // A `Bignum`. The C API has no way to get at integers wider than
+        // 64 bits, so we go through the decimal representation.
+        let digits = value.display_string();
+        digits.parse().map_err(|_| out_of_range(ty, digits))
+    } else {
+        Err(type_mismatch("Integer", &value))
+    }
+}
+
+/// Converts a Rust integer into a Ruby `Integer`.
+///
+/// Values that don't fit into a `Fixnum` become a `Bignum`.
+fn from_integer<T>(value: T) -> Value
+    where T: TryInto<i64> + ToString + Copy {
+    match value.try_into() {
+        Ok(v) if (FIXNUM_MIN..=FIXNUM_MAX).contains(&v) => Value::integer(v),
+        _ => Value::string(value.to_string()).call_no_args("to_i"),
+    }
+}
+
+/// Creates an error for a number that doesn't fit in a Rust type.
+fn out_of_range(ty: &str, value: String) -> MarshallError {
+    MarshallError::OutOfRange { ty: ty.to_owned(), value }
+}
+
+/// Creates an error for a value that is not of the expected Ruby class.
+fn type_mismatch(expected: &str, value: &Value) -> MarshallError {
+    MarshallError::TypeMismatch {
+        expected: expected.to_owned(),
+        got: value.class_name(),
+    }
+}
@@ -0,0 +1,72 @@
+//! Naming of plugged classes and modules in Ruby.
+//!
+//! Classes and modules are nested in Ruby modules named after their Rust
+//! module path, without the crate name. `game::world::Player` becomes
+//! `World::Player`, and a class at the root of a crate is defined at the top
+//! level. A `#[pluggable(namespace = "...")]` option replaces the derived
+//! namespace.
+//!
+//! Items at the same path in different crates, such as `game::Player` and
+//! `editor::Player`, get the same name. The second one to be plugged in fails
+//! with `ErrorKind::NameCollision`, so one of them must be given a namespace.
+
+use plugger_core::{Class, Module, PluggableValue};
+
+/// Gets the nested names of the Ruby class for a pluggable class.
+pub fn class_names(class: &Class) -> Vec<String> {
+    let mut names = namespace_names(class.namespace, &class.path);
+    names.push(class.name.clone());
+    names
+}
+
+/// Gets the nested names of the Ruby class for a data-carrying enum.
+pub fn enum_class_names<E>() -> Vec<String> where E: PluggableValue {
+    let mut names = namespace_names(E::type_namespace(), E::type_path());
+    names.push(E::type_name().to_owned());
+    names
+}
+
+/// Gets the nested names of the Ruby module for a pluggable module.
+///
+/// The functions at the root of a crate go into a module named after the
+/// crate.
+pub fn module_names(module: &Module) -> Vec<String> {
+    let mut names = namespace_names(module.namespace, &module.path);
+    names.push(camel_case(module.path.rsplit("::").next().unwrap()));
+    names
+}
+
+/// Gets the fully qualified name of the Ruby class for a pluggable class.
+pub fn class_name(class: &Class) -> String {
+    class_names(class).join("::")
+}
+
+/// Gets the fully qualified name of the Ruby class for a pluggable class
+/// that is only known by its path and namespace, such as in a signature.
+pub fn class_path_name(path: &str, namespace: Option<&str>) -> String {
+    let mut names = namespace_names(namespace, path);
+    names.push(path.rsplit("::").next().unwrap().to_owned());
+    names.join("::")
+}
+
+/// Gets the names of the Ruby modules that an item is nested in.
+fn namespace_names(namespace: Option<&str>, path: &str) -> Vec<String> {
+    match namespace {
+        Some(namespace) => namespace.split("::").filter(|name| !name.is_empty()).map(str::to_owned).collect(),
+        None => {
+            // Skip the crate name and the name of the item itself.
+            let segments: Vec<&str> = path.split("::").collect();
+            let parents = if segments.len() > 2 { &segments[1..segments.len() - 1] } else { &[] };
+
+            parents.iter().map(|segment| camel_case(segment)).collect()
+        },
+    }
+}
+
+/// Converts a Rust module name such as `game_world` into `GameWorld`.
+fn camel_case(name: &str) -> String {
+    name.split('_').filter(|word| !word.is_empty()).map(|word| {
+        let mut chars = word.chars();
+        chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+    }).collect::<Vec<String>>().concat()
+}
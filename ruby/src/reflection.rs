@@ -0,0 +1,54 @@
+//! Descriptions of plugged methods and fields, for reflection from Ruby.
+//!
+//! Each description is a hash with symbol keys, such as
+//!
+//! ```ruby
+//! { name: :move_by, static: false, returns: nil, docs: "",
+//!   parameters: [{ name: :dx, type: "Option<i32>", ruby_type: "Integer?" }] }
+//! ```
+
+use plugger_core::{Field, Method, MethodOptions, TypeDesc, Value};
+
+use signatures;
+
+/// Describes some methods.
+pub fn methods(methods: &[Method]) -> Value {
+    Value::Array(methods.iter().map(|method| {
+        let parameters = method.parameters.iter().map(|parameter| {
+            entries(vec![
+                ("name", Value::Symbol(parameter.name.clone())),
+                ("type", type_name(&parameter.ty)),
+                ("ruby_type", Value::String(signatures::rbs_type_name(&parameter.ty, &method.options))),
+            ])
+        }).collect();
+
+        entries(vec![
+            ("name", Value::Symbol(method.name.to_owned())),
+            ("parameters", Value::Array(parameters)),
+            ("returns", method.ret.as_ref().map(type_name).unwrap_or(Value::Nil)),
+            ("static", Value::Bool(method.is_static)),
+            ("docs", Value::String(method.docs.to_owned())),
+        ])
+    }).collect())
+}
+
+/// Describes some fields.
+pub fn fields(fields: &[Field]) -> Value {
+    Value::Array(fields.iter().map(|field| {
+        entries(vec![
+            ("name", Value::Symbol(field.name.to_owned())),
+            ("type", type_name(&field.ty)),
+            ("ruby_type", Value::String(signatures::rbs_type_name(&field.ty, &MethodOptions::default()))),
+            ("docs", Value::String(field.docs.to_owned())),
+        ])
+    }).collect())
+}
+
+fn type_name(ty: &TypeDesc) -> Value {
+    Value::String(ty.to_string())
+}
+
+/// Creates a map with symbol keys.
+fn entries(entries: Vec<(&str, Value)>) -> Value {
+    Value::Map(entries.into_iter().map(|(key, value)| (Value::Symbol(key.to_owned()), value)).collect())
+}
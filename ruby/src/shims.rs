@@ -2,6 +2,11 @@
 //!
 //! These methods are hooked into the Ruby VM, which then
 //! dispatch to actual Rust functions.
+//!
+//! Every plugged method is defined directly on its class, with one of these
+//! shims as its implementation. The shim finds the Rust function by the class
+//! and the name of the method being executed, so calling a method is a single
+//! native call.
 
 use rurust::Value;
 use std::mem;
@@ -46,20 +51,22 @@ macro_rules! dispatch {
 }
 
 macro_rules! dispatch_method {
-    ( $func_ptr:expr , $receiver:expr => $method_type:ty => ( $( $arg:expr ),* ) ) => {
+    ( $receiver:expr => $method_type:ty => ( $( $arg:expr ),* ) ) => {
         {
-            let method = helpers::function_pointer($func_ptr);
-            let receiver = helpers::reference_to_struct($receiver);
-            dispatch!( method => $method_type => ( receiver $(, $arg )* ) )
+            let args: &[&str] = &[ $( stringify!($arg) ),* ];
+            let function = helpers::function(helpers::class_of($receiver), args.len());
+            let receiver = helpers::reference_to_struct($receiver, function);
+            dispatch!( function.pointer => $method_type => ( receiver $(, $arg )* ) )
         }
     }
 }
 
 macro_rules! dispatch_function {
-    ( $func_ptr:expr => $method_type:ty => ( $( $arg:expr ),* ) ) => {
+    ( $class:expr => $method_type:ty => ( $( $arg:expr ),* ) ) => {
         {
-            let method = helpers::function_pointer($func_ptr);
-            dispatch!( method => $method_type => ( $( $arg ),* ) )
+            let args: &[&str] = &[ $( stringify!($arg) ),* ];
+            let function = helpers::static_function($class, args.len());
+            dispatch!( function.pointer => $method_type => ( $( $arg ),* ) )
         }
     }
 }
@@ -98,124 +105,185 @@ pub fn ruby_function(arg_count: usize) -> *mut extern fn() -> Value {
     }
 }
 
-pub extern fn ruby_method0(receiver: Value, func_ptr: Value) -> Value {
-    dispatch_method!(func_ptr, receiver => Method => ())
+pub extern fn ruby_method0(receiver: Value) -> Value {
+    dispatch_method!(receiver => Method => ())
 }
 
-pub extern fn ruby_method1(receiver: Value, func_ptr: Value, a1: Value) -> Value {
-    dispatch_method!(func_ptr, receiver => Method1 => (a1))
+pub extern fn ruby_method1(receiver: Value, a1: Value) -> Value {
+    dispatch_method!(receiver => Method1 => (a1))
 }
 
-pub extern fn ruby_method2(receiver: Value, func_ptr: Value, a1: Value, a2: Value) -> Value {
-    dispatch_method!(func_ptr, receiver => Method2 => (a1, a2))
+pub extern fn ruby_method2(receiver: Value, a1: Value, a2: Value) -> Value {
+    dispatch_method!(receiver => Method2 => (a1, a2))
 }
 
-pub extern fn ruby_method3(receiver: Value, func_ptr: Value,
+pub extern fn ruby_method3(receiver: Value,
                            a1: Value, a2: Value, a3: Value) -> Value {
-    dispatch_method!(func_ptr, receiver => Method3 => (a1, a2, a3))
+    dispatch_method!(receiver => Method3 => (a1, a2, a3))
 }
 
-pub extern fn ruby_method4(receiver: Value, func_ptr: Value,
+pub extern fn ruby_method4(receiver: Value,
                            a1: Value, a2: Value, a3: Value, a4: Value) -> Value {
-    dispatch_method!(func_ptr, receiver => Method4 => (a1, a2, a3, a4))
+    dispatch_method!(receiver => Method4 => (a1, a2, a3, a4))
 }
 
-pub extern fn ruby_method5(receiver: Value, func_ptr: Value,
+pub extern fn ruby_method5(receiver: Value,
                            a1: Value, a2: Value, a3: Value, a4: Value,
                            a5: Value) -> Value {
-    dispatch_method!(func_ptr, receiver => Method5 => (a1, a2, a3, a4, a5))
+    dispatch_method!(receiver => Method5 => (a1, a2, a3, a4, a5))
 }
 
-pub extern fn ruby_method6(receiver: Value, func_ptr: Value,
+pub extern fn ruby_method6(receiver: Value,
                            a1: Value, a2: Value, a3: Value, a4: Value,
                            a5: Value, a6: Value) -> Value {
-    dispatch_method!(func_ptr, receiver => Method6 => (a1, a2, a3, a4, a5, a6))
+    dispatch_method!(receiver => Method6 => (a1, a2, a3, a4, a5, a6))
 }
 
-pub extern fn ruby_method7(receiver: Value, func_ptr: Value,
+pub extern fn ruby_method7(receiver: Value,
                            a1: Value, a2: Value, a3: Value, a4: Value,
                            a5: Value, a6: Value, a7: Value) -> Value {
-    dispatch_method!(func_ptr, receiver => Method7 => (a1, a2, a3, a4, a5, a6, a7))
+    dispatch_method!(receiver => Method7 => (a1, a2, a3, a4, a5, a6, a7))
 }
 
-pub extern fn ruby_method8(receiver: Value, func_ptr: Value,
+pub extern fn ruby_method8(receiver: Value,
                            a1: Value, a2: Value, a3: Value, a4: Value,
                            a5: Value, a6: Value, a7: Value, a8: Value) -> Value {
-    dispatch_method!(func_ptr, receiver => Method8 => (a1, a2, a3, a4, a5, a6, a7, a8))
+    dispatch_method!(receiver => Method8 => (a1, a2, a3, a4, a5, a6, a7, a8))
 }
 
-pub extern fn ruby_method9(receiver: Value, func_ptr: Value,
+pub extern fn ruby_method9(receiver: Value,
                            a1: Value, a2: Value, a3: Value, a4: Value,
                            a5: Value, a6: Value, a7: Value, a8: Value, a9: Value) -> Value {
-    dispatch_method!(func_ptr, receiver => Method9 => (a1, a2, a3, a4, a5, a6, a7, a8, a9))
+    dispatch_method!(receiver => Method9 => (a1, a2, a3, a4, a5, a6, a7, a8, a9))
 }
 
-pub extern fn ruby_function0(_receiver: Value, func_ptr: Value) -> Value {
-    dispatch_function!(func_ptr => Function => ())
+pub extern fn ruby_function0(class: Value) -> Value {
+    dispatch_function!(class => Function => ())
 }
 
-pub extern fn ruby_function1(_receiver: Value, func_ptr: Value, a1: Value) -> Value {
-    dispatch_function!(func_ptr => Function1 => (a1))
+pub extern fn ruby_function1(class: Value, a1: Value) -> Value {
+    dispatch_function!(class => Function1 => (a1))
 }
 
-pub extern fn ruby_function2(_receiver: Value, func_ptr: Value, a1: Value, a2: Value) -> Value {
-    dispatch_function!(func_ptr => Function2 => (a1, a2))
+pub extern fn ruby_function2(class: Value, a1: Value, a2: Value) -> Value {
+    dispatch_function!(class => Function2 => (a1, a2))
 }
 
-pub extern fn ruby_function3(_receiver: Value, func_ptr: Value, a1: Value, a2: Value, a3: Value) -> Value {
-    dispatch_function!(func_ptr => Function3 => (a1, a2, a3))
+pub extern fn ruby_function3(class: Value, a1: Value, a2: Value, a3: Value) -> Value {
+    dispatch_function!(class => Function3 => (a1, a2, a3))
 }
 
-pub extern fn ruby_function4(_receiver: Value, func_ptr: Value, a1: Value, a2: Value, a3: Value,
+pub extern fn ruby_function4(class: Value, a1: Value, a2: Value, a3: Value,
                              a4: Value) -> Value {
-    dispatch_function!(func_ptr => Function4 => (a1, a2, a3, a4))
+    dispatch_function!(class => Function4 => (a1, a2, a3, a4))
 }
 
-pub extern fn ruby_function5(_receiver: Value, func_ptr: Value, a1: Value, a2: Value, a3: Value,
+pub extern fn ruby_function5(class: Value, a1: Value, a2: Value, a3: Value,
                              a4: Value, a5: Value) -> Value {
-    dispatch_function!(func_ptr => Function5 => (a1, a2, a3, a4, a5))
+    dispatch_function!(class => Function5 => (a1, a2, a3, a4, a5))
 }
 
-pub extern fn ruby_function6(_receiver: Value, func_ptr: Value, a1: Value, a2: Value, a3: Value,
+pub extern fn ruby_function6(class: Value, a1: Value, a2: Value, a3: Value,
                              a4: Value, a5: Value, a6: Value) -> Value {
-    dispatch_function!(func_ptr => Function6 => (a1, a2, a3, a4, a5, a6))
+    dispatch_function!(class => Function6 => (a1, a2, a3, a4, a5, a6))
 }
 
-pub extern fn ruby_function7(_receiver: Value, func_ptr: Value, a1: Value, a2: Value, a3: Value,
+pub extern fn ruby_function7(class: Value, a1: Value, a2: Value, a3: Value,
                              a4: Value, a5: Value, a6: Value, a7: Value) -> Value {
-    dispatch_function!(func_ptr => Function7 => (a1, a2, a3, a4, a5, a6, a7))
+    dispatch_function!(class => Function7 => (a1, a2, a3, a4, a5, a6, a7))
 }
 
-pub extern fn ruby_function8(_receiver: Value, func_ptr: Value, a1: Value, a2: Value, a3: Value,
+pub extern fn ruby_function8(class: Value, a1: Value, a2: Value, a3: Value,
                              a4: Value, a5: Value, a6: Value, a7: Value,
                              a8: Value) -> Value {
-    dispatch_function!(func_ptr => Function8 => (a1, a2, a3, a4, a5, a6, a7, a8))
+    dispatch_function!(class => Function8 => (a1, a2, a3, a4, a5, a6, a7, a8))
 }
 
-pub extern fn ruby_function9(_receiver: Value, func_ptr: Value, a1: Value, a2: Value, a3: Value,
+pub extern fn ruby_function9(class: Value, a1: Value, a2: Value, a3: Value,
                              a4: Value, a5: Value, a6: Value, a7: Value,
                              a8: Value, a9: Value) -> Value {
-    dispatch_function!(func_ptr => Function9 => (a1, a2, a3, a4, a5, a6, a7, a8, a9))
+    dispatch_function!(class => Function9 => (a1, a2, a3, a4, a5, a6, a7, a8, a9))
 }
 
 mod helpers {
     use super::Receiver;
-    use rurust::Value;
-    use std::mem;
-    use super::FunctionPointer;
+    use plugger_core::MarshallError;
+    use rurust::{self, Value};
+    use data::{self, Function};
+    use {error, ffi};
 
     /// Gets a reference to the Rust struct from the associated Ruby object.
-    pub fn reference_to_struct<'a>(ruby_object: Value) -> &'a mut Receiver {
-        let ptr = ruby_object.call_no_args("object_pointer").to_u64() as usize;
-        let receiver: &mut Receiver = unsafe { mem::transmute(ptr) };
-        receiver
+    ///
+    /// Raises a `TypeError` if `function` is static, or if the object is not
+    /// of the Rust type that `function` is a method of, just like an argument
+    /// of the wrong type.
+    pub fn reference_to_struct<'a>(ruby_object: Value, function: &Function) -> &'a mut Receiver {
+        let object = data::unwrap_object(ruby_object);
+
+        if function.is_static {
+            error::raise(rurust::classes::TypeError(), "a static method can not be called on an object");
+        }
+
+        match function.receiver {
+            Some((type_id, _)) if type_id == object.type_id => unsafe { &mut *object.pointer },
+            ref receiver => {
+                let message = MarshallError::TypeMismatch {
+                    expected: receiver.as_ref().map(|receiver| receiver.1.clone()).unwrap_or_default(),
+                    got: object.type_name.to_owned(),
+                }.to_string();
+                error::raise(rurust::classes::TypeError(), message)
+            },
+        }
     }
 
-    /// Creates a function from a function pointer given from Ruby.
-    pub fn function_pointer(function_pointer: Value) -> FunctionPointer {
-        let ptr = function_pointer.to_u64() as usize;
+    /// Gets the class that an instance method was called on.
+    pub fn class_of(receiver: Value) -> Value {
+        Value::from(unsafe { ffi::rb_obj_class(receiver.0) })
+    }
 
-        unsafe { mem::transmute(ptr) }
+    /// Gets the Rust function which implements the method that is currently
+    /// executing on `class`, or on the closest superclass that defines it.
+    ///
+    /// Raises a `TypeError` if the method isn't a plugged Rust function, and an
+    /// `ArgumentError` if the function does not take `arg_count` arguments, as
+    /// calling it would otherwise corrupt the stack.
+    pub fn function(class: Value, arg_count: usize) -> &'static Function {
+        // The method is looked up by the name it was defined with, so aliases
+        // still find the right function.
+        let method = Value::from(unsafe { ffi::rb_id2sym(ffi::rb_frame_this_func()) });
+
+        // Ruby subclasses inherit plugged methods, which are only registered
+        // on the class that defined them.
+        let mut owner = class;
+        let function = loop {
+            if let Some(function) = data::function(owner, method) {
+                break function;
+            }
+
+            owner = if owner.is_class() { Value::from(unsafe { ffi::rb_class_superclass(owner.0) }) } else { Value::nil() };
+            if owner.is_nil() {
+                error::raise(rurust::classes::TypeError(), "not a plugged method");
+            }
+        };
+
+        if function.arg_count != arg_count {
+            error::raise(rurust::classes::ArgError(),
+                         format!("wrong number of arguments (given {}, expected {})",
+                                 arg_count, function.arg_count));
+        }
+        function
     }
-}
 
+    /// Gets the Rust function which implements the static method that is
+    /// currently executing on `class`.
+    ///
+    /// Raises a `TypeError` if the function needs a receiver.
+    pub fn static_function(class: Value, arg_count: usize) -> &'static Function {
+        let function = function(class, arg_count);
+
+        if !function.is_static {
+            error::raise(rurust::classes::TypeError(), "a method can not be called without an object");
+        }
+        function
+    }
+}
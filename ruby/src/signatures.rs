@@ -0,0 +1,287 @@
+//! Generates RBS type signatures and YARD-documented stubs for pluggable
+//! classes.
+//!
+//! Plugged methods are dispatched dynamically, so editors and type checkers
+//! such as Steep and Solargraph can't see them. These files describe them
+//! instead.
+//!
+//! ```ignore
+//! plugger_ruby::signatures::write(&[Player::class()], Path::new("scripts"))?;
+//! ```
+
+use plugger_core::{Class, Method, MethodOptions, TypeDesc};
+use naming;
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// The signatures of the classes that every plugged class derives from.
+pub const RBS_PRELUDE: &str = "\
+# Generated by plugger. Do not edit.
+
+class PluggerObject
+end
+
+class PluggerEnum
+  attr_reader variant: Symbol
+  attr_reader fields: Hash[Symbol, untyped]
+end
+";
+
+const HEADER: &str = "# Generated by plugger. Do not edit.\n";
+
+/// A Ruby type, as far as the signatures care.
+enum RubyType
+{
+    /// The result of a method which returns nothing useful.
+    Void,
+    Bool,
+    /// Any object, which Rust reads as a bool by its truthiness.
+    Boolish,
+    Class(String),
+    Array(Box<RubyType>),
+    Tuple(Vec<RubyType>),
+    Hash(Box<RubyType>, Box<RubyType>),
+    Optional(Box<RubyType>),
+    Union(Vec<RubyType>),
+    Untyped,
+}
+
+/// Writes the signatures and stubs of some classes into a directory.
+///
+/// The RBS signatures are written into `sig/`, and the YARD stubs into
+/// `stubs/`, with one file per class named after its Ruby name, so
+/// `Game::Player` is written to `sig/game/player.rbs`. Fails before writing
+/// anything if two classes have the same Ruby name.
+pub fn write(classes: &[Class], directory: &Path) -> io::Result<()> {
+    let mut file_paths: Vec<PathBuf> = Vec::new();
+    for class in classes {
+        let file_path = file_path(class);
+
+        if let Some(existing) = file_paths.iter().position(|existing| *existing == file_path) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!(
+                "{} and {} are both named {} in Ruby", classes[existing].path, class.path, naming::class_name(class))));
+        }
+        file_paths.push(file_path);
+    }
+
+    let sig_directory = directory.join("sig");
+    let stub_directory = directory.join("stubs");
+    fs::create_dir_all(&sig_directory)?;
+    fs::create_dir_all(&stub_directory)?;
+
+    File::create(sig_directory.join("plugger.rbs"))?.write_all(RBS_PRELUDE.as_bytes())?;
+
+    for (class, file_path) in classes.iter().zip(file_paths) {
+        let sig_path = sig_directory.join(&file_path).with_extension("rbs");
+        let stub_path = stub_directory.join(&file_path).with_extension("rb");
+        fs::create_dir_all(sig_path.parent().unwrap())?;
+        fs::create_dir_all(stub_path.parent().unwrap())?;
+
+        File::create(sig_path)?.write_all(rbs(class).as_bytes())?;
+        File::create(stub_path)?.write_all(yard_stub(class).as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Generates the RBS signature of a class.
+pub fn rbs(class: &Class) -> String {
+    let mut output = format!("{}\n", HEADER);
+    write_namespace(&mut output, class);
+    output.push_str(&format!("class {} < PluggerObject\n", naming::class_name(class)));
+
+    for constant in class.constants.iter() {
+        let options = return_options(&constant.getter);
+        output.push_str(&format!("  {}: {}\n", constant.name, rbs_type(&ruby_type(&constant.ty, &options))));
+    }
+
+    for method in class.methods.iter() {
+        let parameters: Vec<String> = method.parameters.iter().map(|parameter| {
+            format!("{} {}", rbs_type(&ruby_type(&parameter.ty, &method.options)), parameter.name)
+        }).collect();
+
+        output.push_str(&format!("  def {}{}: ({}) -> {}\n",
+                                 receiver(method), method.name,
+                                 parameters.join(", "), rbs_type(&return_type(method))));
+    }
+
+    output.push_str("end\n");
+    output
+}
+
+/// Generates a Ruby stub of a class, with its methods documented for YARD.
+pub fn yard_stub(class: &Class) -> String {
+    let mut output = format!("{}\n", HEADER);
+    write_namespace(&mut output, class);
+    write_doc_comment(&mut output, "", class.docs);
+    output.push_str(&format!("# @see {}\nclass {} < PluggerObject\n", class.path, naming::class_name(class)));
+
+    for (index, method) in class.methods.iter().enumerate() {
+        if index != 0 { output.push('\n'); }
+
+        write_doc_comment(&mut output, "  ", method.docs);
+        for parameter in method.parameters.iter() {
+            let ty = ruby_type(&parameter.ty, &method.options);
+            output.push_str(&format!("  # @param {} [{}]\n", parameter.name, yard_type(&ty)));
+        }
+        output.push_str(&format!("  # @return [{}]\n", yard_type(&return_type(method))));
+
+        let parameters: Vec<&str> = method.parameters.iter().map(|parameter| &parameter.name[..]).collect();
+        output.push_str(&format!("  def {}{}({}); end\n", receiver(method), method.name, parameters.join(", ")));
+    }
+
+    output.push_str("end\n");
+    output
+}
+
+/// Declares the modules that a class is nested in, outermost first.
+///
+/// RBS and Ruby both need them to exist before `class Game::Player` can be
+/// declared.
+fn write_namespace(output: &mut String, class: &Class) {
+    let names = naming::class_names(class);
+
+    for length in 1..names.len() {
+        output.push_str(&format!("module {}\nend\n\n", names[..length].join("::")));
+    }
+}
+
+/// Gets the path of the files of a class, relative to the directory they are
+/// written into and without an extension.
+fn file_path(class: &Class) -> PathBuf {
+    naming::class_names(class).iter().map(|name| snake_case(name)).collect()
+}
+
+/// Writes documentation as Ruby comments, followed by a blank comment line.
+fn write_doc_comment(output: &mut String, indent: &str, docs: &str) {
+    if docs.is_empty() { return; }
+
+    for line in docs.lines() {
+        if line.is_empty() {
+            output.push_str(&format!("{}#\n", indent));
+        } else {
+            output.push_str(&format!("{}# {}\n", indent, line));
+        }
+    }
+    output.push_str(&format!("{}#\n", indent));
+}
+
+/// Gets the RBS name of the Ruby type that a Rust type is marshalled as.
+pub fn rbs_type_name(ty: &TypeDesc, options: &MethodOptions) -> String {
+    rbs_type(&ruby_type(ty, options))
+}
+
+fn receiver(method: &Method) -> &'static str {
+    if method.is_static { "self." } else { "" }
+}
+
+fn return_type(method: &Method) -> RubyType {
+    match method.ret {
+        Some(ref ty) => ruby_type(ty, &return_options(method)),
+        None => RubyType::Void,
+    }
+}
+
+/// Gets the options that apply to the values a method returns.
+///
+/// Symbols are only coerced into strings, and objects into bools, when they
+/// are given to Rust.
+fn return_options(method: &Method) -> MethodOptions {
+    MethodOptions { coerce_symbols: false, strict_bool: true, ..method.options }
+}
+
+/// Maps a Rust type to the Ruby type it is marshalled as.
+fn ruby_type(ty: &TypeDesc, options: &MethodOptions) -> RubyType {
+    let ruby_type = |ty: &TypeDesc| ruby_type(ty, options);
+
+    match *ty {
+        TypeDesc::Unit => RubyType::Void,
+        TypeDesc::Primitive("bool") if options.strict_bool => RubyType::Bool,
+        TypeDesc::Primitive("bool") => RubyType::Boolish,
+        TypeDesc::Primitive("f32") | TypeDesc::Primitive("f64") => RubyType::Class("Float".to_owned()),
+        TypeDesc::Primitive("str") | TypeDesc::Primitive("String") if options.coerce_symbols => RubyType::Union(vec![
+            RubyType::Class("String".to_owned()),
+            RubyType::Class("Symbol".to_owned()),
+        ]),
+        TypeDesc::Primitive("char") | TypeDesc::Primitive("str") | TypeDesc::Primitive("String") => {
+            RubyType::Class("String".to_owned())
+        },
+        TypeDesc::Primitive(..) => RubyType::Class("Integer".to_owned()),
+        TypeDesc::Reference { ref ty, .. } => ruby_type(ty),
+        // Bytes are marshalled as binary strings.
+        TypeDesc::Slice(ref elem) if **elem == TypeDesc::Primitive("u8") => RubyType::Class("String".to_owned()),
+        TypeDesc::Slice(ref elem) | TypeDesc::Array { ref elem, .. } => RubyType::Array(Box::new(ruby_type(elem))),
+        TypeDesc::Tuple(ref elems) => RubyType::Tuple(elems.iter().map(ruby_type).collect()),
+        TypeDesc::Generic { name, ref args } => match (name, &args[..]) {
+            ("Vec", [TypeDesc::Primitive("u8")]) => RubyType::Class("String".to_owned()),
+            ("Vec", [elem]) => RubyType::Array(Box::new(ruby_type(elem))),
+            ("Option", [inner]) => RubyType::Optional(Box::new(ruby_type(inner))),
+            // Errors are raised as exceptions.
+            ("Result", [ok, _]) => ruby_type(ok),
+            ("Cow", [inner]) => ruby_type(inner),
+            ("HashMap", [key, value]) | ("BTreeMap", [key, value]) => {
+                let key = if options.symbol_keys && *key == TypeDesc::Primitive("String") {
+                    RubyType::Class("Symbol".to_owned())
+                } else {
+                    ruby_type(key)
+                };
+                RubyType::Hash(Box::new(key), Box::new(ruby_type(value)))
+            },
+            _ => RubyType::Untyped,
+        },
+        // Plugged classes are referred to by their fully qualified name.
+        TypeDesc::Class { path, namespace } => RubyType::Class(naming::class_path_name(path, namespace)),
+        // Fieldless variants are symbols, and the others are enum objects.
+        TypeDesc::Value(..) => RubyType::Union(vec![
+            RubyType::Class("Symbol".to_owned()),
+            RubyType::Class("PluggerEnum".to_owned()),
+        ]),
+        TypeDesc::Named(..) => RubyType::Untyped,
+    }
+}
+
+fn rbs_type(ty: &RubyType) -> String {
+    match *ty {
+        RubyType::Void => "void".to_owned(),
+        RubyType::Bool => "bool".to_owned(),
+        RubyType::Boolish => "boolish".to_owned(),
+        RubyType::Class(ref name) => name.clone(),
+        RubyType::Array(ref elem) => format!("Array[{}]", rbs_type(elem)),
+        RubyType::Tuple(ref elems) => format!("[{}]", elems.iter().map(rbs_type).collect::<Vec<_>>().join(", ")),
+        RubyType::Hash(ref key, ref value) => format!("Hash[{}, {}]", rbs_type(key), rbs_type(value)),
+        RubyType::Optional(ref inner) => match **inner {
+            RubyType::Union(..) => format!("({})?", rbs_type(inner)),
+            RubyType::Void => "nil".to_owned(),
+            _ => format!("{}?", rbs_type(inner)),
+        },
+        RubyType::Union(ref types) => types.iter().map(rbs_type).collect::<Vec<_>>().join(" | "),
+        RubyType::Untyped => "untyped".to_owned(),
+    }
+}
+
+fn yard_type(ty: &RubyType) -> String {
+    match *ty {
+        RubyType::Void => "void".to_owned(),
+        RubyType::Bool => "Boolean".to_owned(),
+        RubyType::Boolish => "Object".to_owned(),
+        RubyType::Class(ref name) => name.clone(),
+        RubyType::Array(ref elem) => format!("Array<{}>", yard_type(elem)),
+        RubyType::Tuple(ref elems) => format!("Array({})", elems.iter().map(yard_type).collect::<Vec<_>>().join(", ")),
+        RubyType::Hash(ref key, ref value) => format!("Hash{{{} => {}}}", yard_type(key), yard_type(value)),
+        RubyType::Optional(ref inner) => format!("{}, nil", yard_type(inner)),
+        RubyType::Union(ref types) => types.iter().map(yard_type).collect::<Vec<_>>().join(", "),
+        RubyType::Untyped => "Object".to_owned(),
+    }
+}
+
+/// Converts a class name such as `GameState` into `game_state`.
+fn snake_case(name: &str) -> String {
+    let mut output = String::new();
+
+    for (index, c) in name.chars().enumerate() {
+        if c.is_uppercase() && index != 0 { output.push('_'); }
+        output.extend(c.to_lowercase());
+    }
+    output
+}
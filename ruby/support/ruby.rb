@@ -1,67 +1,102 @@
-# A mixin that makes an object seem like it contains Rust methods.
-module Dispatcher
-  def methods
-    super.map { |method| ((/(.*)_internal/ =~ method.to_s) ? $1 : method).to_sym }
+# Describes the Rust methods behind a plugged class or module.
+module PluggerReflection
+  # Gets the documentation of a method, or of the class or module
+  # itself if no method is given.
+  def help(name = nil)
+    return @rust_class_docs || '' if name.nil?
+
+    (@rust_docs || {}).fetch(name.to_sym) do
+      raise ArgumentError, "no method named '#{name}'"
+    end
   end
 
-  def method_missing(name, *original_args)
-    return super unless methods.include?(name)
-    method_pointer = self.pointer_to_function(name)
+  # Describes the Rust methods.
+  #
+  # Each method is a hash with the keys `:name`, `:parameters`, `:returns`,
+  # `:static` and `:docs`. Each parameter is a hash with the keys `:name`,
+  # `:type` and `:ruby_type`.
+  def rust_methods
+    @rust_methods || []
+  end
 
-    if method_pointer
-      # we have C functions plugged in, with the '_internal' suffix.
-      # here, we call the internal shim and give it the pointer
-      # to the Rust function.
-      internal_method = "#{name}_internal".to_sym
+  # Describes the Rust fields.
+  #
+  # Each field is a hash with the keys `:name`, `:type`, `:ruby_type`
+  # and `:docs`.
+  def rust_fields
+    @rust_fields || []
+  end
+end
 
-      validate_arguments!(method(internal_method), original_args)
+# The base class of all plugged Rust objects.
+#
+# Instances wrap a pointer to the Rust object as native data, and so
+# can only be created from Rust.
+class PluggerObject
+  extend PluggerReflection
 
-      arguments = [method_pointer] + original_args
+  private_class_method :new
 
-      send(internal_method, *arguments)
-    else
-      super
-    end
+  # Gets the documentation of a method, or of the class itself.
+  # Alias of the class method.
+  def help(name = nil)
+    self.class.help(name)
   end
 
-  private
+  # Alias of the class method.
+  def rust_methods
+    self.class.rust_methods
+  end
 
-  # Raise an ArgumentError if the given arguments don't match the callee.
-  #
-  # We check this in Rubyland because if we call the internal method
-  # and the arguments are wrong, the ArgumentError message will have
-  # the hidden function pointer included.
-  def validate_arguments!(method, original_args)
-    # we don't want to include the hidden method pointer we pass
-    non_self_arg_count = method.arity - 1
-
-    if non_self_arg_count != original_args.size
-      raise ArgumentError, "wrong number of arguments (given #{original_args.size}, expected #{non_self_arg_count})"
-    end
+  # Alias of the class method.
+  def rust_fields
+    self.class.rust_fields
   end
 end
 
-class PluggerObject
-  include Dispatcher # dispatch methods
-  extend Dispatcher  # dispatch singleton methods
+# The base class of data-carrying Rust enums.
+#
+# The variant is a symbol, and each field of the variant
+# can be read through an accessor of the same name.
+class PluggerEnum
+  attr_reader :variant, :fields
+
+  def initialize(variant, fields = {})
+    @variant = variant
+    @fields = fields.freeze
+
+    fields.each_key do |name|
+      define_singleton_method(name) { @fields[name] }
+    end
+  end
 
-  attr_reader :object_pointer
+  def ==(other)
+    other.class == self.class && other.variant == variant && other.fields == fields
+  end
 
-  def initialize(object_pointer)
-    @object_pointer = object_pointer
+  def inspect
+    field_list = fields.map { |name, value| "#{name}=#{value.inspect}" }.join(', ')
+    "#<#{self.class.name}::#{variant} #{field_list}>"
   end
+end
 
-  protected
+# Runs scripts with plugged objects as local variables.
+module PluggerScript
+  @locals = {}
 
-  # Gets a pointer to a function.
-  # Alias of the class method.
-  def pointer_to_function(name)
-    self.class.pointer_to_function(name)
+  # Runs the code given by Rust, in a new scope at the top level.
+  def self.run
+    code = @code
+    @code = nil
+
+    scope = new_scope
+    @locals.each { |name, value| scope.local_variable_set(name, value) }
+    scope.eval(code, '(script)')
   end
 
-  # Gets a pointer to a function.
-  def self.pointer_to_function(name)
-    pointer_const_name = name.to_s.upcase
-    self.const_get(pointer_const_name)
+  # Gets a binding of the main object without any local variables.
+  def self.new_scope
+    TOPLEVEL_BINDING.receiver.instance_eval { binding }
   end
+  private_class_method :new_scope
 end
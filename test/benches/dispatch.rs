@@ -0,0 +1,108 @@
+//! Benchmarks calling plugged Rust methods from Ruby.
+//!
+//! Each iteration makes `CALLS` calls from inside Ruby, so that parsing the
+//! script doesn't drown out the cost of the calls themselves.
+//!
+//! `method_missing_dispatch` emulates how plugged methods used to be
+//! dispatched, as a baseline for the others.
+
+#![feature(test)]
+#![feature(plugin)]
+#![plugin(plugger)]
+
+extern crate test;
+extern crate plugger_core;
+extern crate plugger_ruby;
+
+use plugger_ruby::Ruby;
+use test::Bencher;
+
+// The test suite is only built for tests, so the bench has its own fixtures.
+#[pluggable]
+#[derive(Default)]
+pub struct Player {
+    x: i32,
+}
+
+#[pluggable]
+impl Player {
+    pub fn x(&self) -> i32 { self.x }
+
+    pub fn move_by(&mut self, dx: i32) { self.x += dx; }
+}
+
+#[pluggable]
+#[derive(Default)]
+pub struct Enemy;
+
+#[pluggable]
+impl Enemy {
+    pub fn max_health() -> u32 { 100 }
+}
+
+const CALLS: usize = 10_000;
+
+fn repeat(call: &str) -> String {
+    format!("i = 0; while i < {}; {}; i += 1; end", CALLS, call)
+}
+
+#[bench]
+fn method_without_arguments(b: &mut Bencher) {
+    let mut ruby = Ruby::new().unwrap();
+    let mut player = Player::default();
+    ruby.plug("player", &mut player).unwrap();
+
+    let code = repeat("PLAYER.x");
+    b.iter(|| ruby.eval(&code).unwrap());
+}
+
+#[bench]
+fn method_with_arguments(b: &mut Bencher) {
+    let mut ruby = Ruby::new().unwrap();
+    let mut player = Player::default();
+    ruby.plug("player", &mut player).unwrap();
+
+    let code = repeat("PLAYER.move_by(1)");
+    b.iter(|| ruby.eval(&code).unwrap());
+}
+
+#[bench]
+fn static_method(b: &mut Bencher) {
+    let mut ruby = Ruby::new().unwrap();
+    let mut enemy = Enemy::default();
+    ruby.plug("enemy", &mut enemy).unwrap();
+
+    let code = repeat("Enemy.max_health");
+    b.iter(|| ruby.eval(&code).unwrap());
+}
+
+#[bench]
+fn method_missing_dispatch(b: &mut Bencher) {
+    let mut ruby = Ruby::new().unwrap();
+    let mut player = Player::default();
+    ruby.plug("player", &mut player).unwrap();
+
+    // The old dispatcher mapped every method name and checked the arity in
+    // Ruby before calling an `_internal` method with the Rust function.
+    ruby.eval("
+        class MethodMissingPlayer
+          def x_internal(player); player.x; end
+
+          def methods
+            super.map { |method| ((/(.*)_internal/ =~ method.to_s) ? $1 : method).to_sym }
+          end
+
+          def method_missing(name, *args)
+            return super unless methods.include?(name)
+            internal_method = :\"#{name}_internal\"
+            raise ArgumentError if method(internal_method).arity - 1 != args.size
+            send(internal_method, PLAYER, *args)
+          end
+        end
+
+        METHOD_MISSING_PLAYER = MethodMissingPlayer.new
+    ").unwrap();
+
+    let code = repeat("METHOD_MISSING_PLAYER.x");
+    b.iter(|| ruby.eval(&code).unwrap());
+}
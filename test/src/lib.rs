@@ -5,9 +5,29 @@
 
 extern crate plugger_ruby;
 extern crate plugger_core;
+#[macro_use]
+extern crate serde_derive;
 
 use plugger_ruby::{Ruby, Value};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 
+/// A plain data type that crosses the boundary through `serde`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    volume: u8,
+    difficulty: Difficulty,
+    keys: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Custom { damage: f32 },
+}
+
+/// Someone playing the game.
 #[pluggable]
 #[derive(Debug)]
 pub struct Player {
@@ -33,7 +53,254 @@ impl Player {
         }.name.clone()
     }
 
+    /// Moves the player one step to the left.
+    ///
+    /// Players can't move through walls.
     pub fn move_left(&mut self) { self.x += 1; }
+
+    pub fn nickname(&self) -> Option<String> {
+        if self.name.len() > 3 { Some(self.name[..3].to_owned()) } else { None }
+    }
+
+    pub fn move_by(&mut self, dx: Option<i32>) { self.x += dx.unwrap_or(1); }
+
+    pub fn position(&self) -> [i32; 3] { [self.x, self.y, self.z] }
+
+    pub fn set_position(&mut self, position: [i32; 3]) {
+        self.x = position[0];
+        self.y = position[1];
+        self.z = position[2];
+    }
+
+    pub fn total(&self, values: &[i32]) -> i32 { values.iter().sum() }
+
+    pub fn name_parts(&self) -> Vec<String> {
+        self.name.split_whitespace().map(|part| part.to_owned()).collect()
+    }
+
+    pub fn join_names(&self, names: Vec<String>) -> String { names.join(", ") }
+
+    #[pluggable(symbol_keys)]
+    pub fn coordinates(&self) -> HashMap<String, i32> {
+        vec![("x", self.x), ("y", self.y), ("z", self.z)].into_iter()
+            .map(|(axis, value)| (axis.to_owned(), value))
+            .collect()
+    }
+
+    pub fn sum_values(&self, values: BTreeMap<String, i32>) -> i32 {
+        values.values().sum()
+    }
+
+    pub fn bounds(&self) -> (i32, String) { (self.x, self.name.clone()) }
+
+    pub fn describe(&self, pair: (String, u8)) -> String { format!("{} {}", pair.0, pair.1) }
+
+    pub fn state(&self) -> State {
+        if self.x > 0 { State::Running } else { State::Idle }
+    }
+
+    pub fn is_in_state(&self, state: State) -> bool { self.state() == state }
+
+    pub fn perform(&mut self, command: Command) -> Command {
+        match command {
+            Command::MoveTo { x, y } => { self.x = x; self.y = y; },
+            _ => (),
+        }
+        command
+    }
+
+    pub fn greet(&self, greeting: &str) -> String { format!("{}, {}", greeting, self.name) }
+
+    #[pluggable(coerce_symbols)]
+    pub fn salute(&self, greeting: &str) -> String { self.greet(greeting) }
+
+    pub fn name_str(&self) -> &str { &self.name }
+
+    pub fn initial(&self) -> char { self.name.chars().next().unwrap() }
+
+    pub fn has_initial(&self, initial: char) -> bool { self.initial() == initial }
+
+    pub fn shout<'a>(&self, words: Cow<'a, str>) -> Cow<'a, str> {
+        Cow::Owned(words.to_uppercase())
+    }
+
+    pub fn distance(&self, steps: usize) -> isize { self.x as isize - steps as isize }
+
+    pub fn huge(&self) -> u128 { u64::max_value() as u128 * 4 }
+
+    pub fn halve(&self, value: i128) -> i128 { value / 2 }
+
+    pub fn id(&self) -> u64 { u64::max_value() - 1 }
+
+    pub fn is_id(&self, id: u64) -> bool { self.id() == id }
+
+    pub fn set_z(&mut self, z: u8) { self.z = z as i32; }
+
+    pub fn either(&self, a: bool, b: bool) -> bool { a || b }
+
+    #[pluggable(strict_bool)]
+    pub fn both(&self, a: bool, b: bool) -> bool { a && b }
+
+    #[pluggable(serde)]
+    pub fn settings(&self) -> Settings {
+        Settings {
+            volume: 11,
+            difficulty: Difficulty::Custom { damage: 1.5 },
+            keys: vec!["w".to_owned(), "a".to_owned()],
+        }
+    }
+
+    #[pluggable(serde)]
+    pub fn volume_of(&self, settings: Settings) -> u8 { settings.volume }
+
+    pub fn save(&self) -> Vec<u8> { vec![0, 159, 146, 150, 255] }
+
+    pub fn checksum(&self, data: &[u8]) -> u32 { data.iter().map(|&b| b as u32).sum() }
+
+    pub fn teleport(&mut self, x: i32) -> Result<i32, String> {
+        if x < 0 {
+            Err("cannot teleport below zero".to_owned())
+        } else {
+            self.x = x;
+            Ok(x)
+        }
+    }
+
+    pub fn climb(&mut self, z: i32) -> Result<i32, Blocked> {
+        if z > 10 {
+            Err(Blocked { height: z })
+        } else {
+            self.z = z;
+            Ok(z)
+        }
+    }
+}
+
+/// Returned when a player can't get somewhere.
+#[derive(Debug)]
+pub struct Blocked {
+    height: i32,
+}
+
+impl fmt::Display for Blocked {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "cannot climb to {}", self.height)
+    }
+}
+
+#[pluggable]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum State {
+    Idle,
+    Running,
+}
+
+#[pluggable]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Wait,
+    MoveTo { x: i32, y: i32 },
+    Say(String),
+}
+
+#[pluggable]
+#[derive(Debug, Default)]
+pub struct Enemy {
+    health: u32,
+    /// The settings the enemy was made with, if it was made by a player.
+    pub settings: Option<Settings>,
+}
+
+#[pluggable]
+impl Enemy {
+    /// The health of a new enemy.
+    pub const MAX_HEALTH: u32 = 100;
+
+    pub fn health(&self) -> u32 { self.health }
+
+    pub fn max_health() -> u32 { 100 }
+}
+
+/// Has a constant that Ruby won't accept as a constant name.
+#[pluggable]
+pub struct Misnamed;
+
+#[pluggable]
+impl Misnamed {
+    #[allow(non_upper_case_globals)]
+    pub const limit: u32 = 1;
+}
+
+/// Things that happen in the game world.
+#[pluggable]
+pub mod world {
+    /// Creates an enemy somewhere in the world.
+    pub fn spawn_enemy(x: i32, y: i32) -> String { format!("enemy at {}, {}", x, y) }
+
+    pub fn gravity() -> f64 { 9.8 }
+
+    pub static NAME: &'static str = "Earth";
+
+    fn secret() -> u32 { 42 }
+}
+
+#[pluggable]
+pub fn version() -> String { "1.0".to_owned() }
+
+/// Takes both the options of its module and its own.
+#[pluggable(namespace = "Tools", strict_bool)]
+pub fn toggle(on: bool) -> bool { !on }
+
+#[pluggable]
+pub const MAX_PLAYERS: u8 = 4;
+
+pub mod game {
+    use plugger_core;
+
+    /// A player of the game, rather than in it.
+    #[pluggable]
+    #[derive(Default)]
+    pub struct Player {
+        score: u32,
+    }
+
+    #[pluggable]
+    impl Player {
+        pub fn score(&self) -> u32 { self.score }
+
+        pub fn beats(&self, other: &Player) -> bool { self.score > other.score }
+    }
+
+    /// Named like a core Ruby class.
+    #[pluggable(namespace = "Items")]
+    #[derive(Default)]
+    pub struct Hash {
+        digest: u64,
+    }
+
+    #[pluggable]
+    impl Hash {
+        pub fn digest(&self) -> u64 { self.digest }
+    }
+
+    /// Named like a class at the root of the crate.
+    #[pluggable(namespace = "")]
+    pub struct Enemy;
+
+    #[pluggable(namespace = "Game::Orders")]
+    pub enum Order {
+        Attack { target: u32 },
+        Retreat,
+    }
+
+    #[pluggable]
+    impl Enemy { }
+}
+
+/// Named like a core Ruby module.
+#[pluggable]
+pub mod math {
+    pub fn double(x: i32) -> i32 { x * 2 }
 }
 
 impl Default for Player {
@@ -61,13 +328,46 @@ fn plugger() {
     correctly_marshalls_rust_strings(&mut ruby);
     accepts_same_rust_object_as_non_self_argument(&mut ruby);
     returns_nil_if_no_retvalue_in_rust(&mut ruby);
+    rejects_forged_objects(&mut ruby);
+    rejects_objects_of_the_wrong_type(&mut ruby);
+    marshalls_options_as_nil_or_value(&mut ruby);
+    raises_exceptions_for_rust_errors(&mut ruby);
+    marshalls_collections_as_arrays(&mut ruby);
+    reports_the_index_of_bad_elements(&mut ruby);
+    marshalls_maps_as_hashes(&mut ruby);
+    marshalls_tuples_as_arrays(&mut ruby);
+    marshalls_fieldless_enums_as_symbols(&mut ruby);
+    marshalls_data_enums_as_objects(&mut ruby);
+    marshalls_strs_and_chars(&mut ruby);
+    marshalls_pointer_sized_and_128_bit_integers(&mut ruby);
+    marshalls_64_bit_integers_losslessly(&mut ruby);
+    raises_range_errors_for_integers_that_do_not_fit(&mut ruby);
+    marshalls_bytes_as_binary_strings(&mut ruby);
+    marshalls_strings_as_utf8(&mut ruby);
+    coerces_symbols_to_strings_when_asked(&mut ruby);
+    follows_ruby_truthiness_for_bools(&mut ruby);
+    rejects_non_booleans_when_strict(&mut ruby);
+    marshalls_serde_types(&mut ruby);
+    converts_values_to_language_neutral_values(&mut ruby);
+    invokes_methods_by_name();
+    describes_method_signatures();
+    exports_classes_as_json_schema();
+    generates_ruby_signatures();
+    exposes_docs_through_help(&mut ruby);
+    reflects_over_rust_methods(&mut ruby);
+    defines_rust_methods_directly(&mut ruby);
+    registers_classes_once(&mut ruby);
+    plugs_free_functions_into_modules(&mut ruby);
+    exposes_constants(&mut ruby);
+    namespaces_classes_by_module_path(&mut ruby);
+    binds_plugged_objects(&mut ruby);
 }
 
 /// We should be able to call simple Rust methods from Ruby.
 fn can_access_rust_methods_from_ruby(ruby: &mut Ruby) {
     let mut player = Player::default();
 
-    ruby.plug("player", &mut player);
+    ruby.plug("player", &mut player).unwrap();
 
     assert_eq!(Value::integer(player.x as _), ruby.eval("PLAYER.x").unwrap());
     assert_eq!(Value::integer(player.y as _), ruby.eval("PLAYER.y").unwrap());
@@ -78,7 +378,7 @@ fn can_access_rust_methods_from_ruby(ruby: &mut Ruby) {
 fn correctly_marshalls_rust_strings(ruby: &mut Ruby) {
     let mut player = Player::default();
 
-    ruby.plug("player", &mut player);
+    ruby.plug("player", &mut player).unwrap();
 
     assert_eq!(Value::string(player.name()), ruby.eval("PLAYER.name").unwrap());
 }
@@ -88,8 +388,8 @@ fn accepts_same_rust_object_as_non_self_argument(ruby: &mut Ruby) {
     let mut long_name_player = Player { name: "long as shit name".to_owned(), ..Player::default() };
     let mut short_name_player = Player { name: "bar".to_owned(), ..Player::default() };
 
-    ruby.plug("long_name_player", &mut long_name_player);
-    ruby.plug("short_name_player", &mut short_name_player);
+    ruby.plug("long_name_player", &mut long_name_player).unwrap();
+    ruby.plug("short_name_player", &mut short_name_player).unwrap();
 
     let longest_name = Value::string(long_name_player.name);
     assert_eq!(longest_name, ruby.eval("SHORT_NAME_PLAYER.longest_name(LONG_NAME_PLAYER)").unwrap());
@@ -100,8 +400,697 @@ fn accepts_same_rust_object_as_non_self_argument(ruby: &mut Ruby) {
 fn returns_nil_if_no_retvalue_in_rust(ruby: &mut Ruby) {
     let mut player = Player::default();
 
-    ruby.plug("player", &mut player);
+    ruby.plug("player", &mut player).unwrap();
 
     assert_eq!(Value::nil(), ruby.eval("PLAYER.move_left").unwrap());
 }
 
+
+/// Scripts should not be able to make Rust dereference arbitrary pointers.
+fn rejects_forged_objects(ruby: &mut Ruby) {
+    let mut player = Player::default();
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert!(ruby.eval("Player.new(12345)").is_err());
+    assert!(ruby.eval("PLAYER.longest_name(12345)").is_err());
+    assert!(ruby.eval("PLAYER.longest_name(Object.new)").is_err());
+    assert!(ruby.eval("Player::X = 12345; PLAYER.x").is_ok());
+}
+
+/// Passing an object of one pluggable type where another is expected should
+/// raise a `TypeError`.
+fn rejects_objects_of_the_wrong_type(ruby: &mut Ruby) {
+    let mut player = Player::default();
+    let mut enemy = Enemy::default();
+
+    ruby.plug("player", &mut player).unwrap();
+    ruby.plug("enemy", &mut enemy).unwrap();
+
+    assert_eq!(Value::string("expected Player, got Enemy"),
+               ruby.eval("begin; PLAYER.longest_name(ENEMY); rescue TypeError => e; e.message; end").unwrap());
+    assert_eq!(Value::string("expected Player, got Integer"),
+               ruby.eval("begin; PLAYER.longest_name(1); rescue TypeError => e; e.message; end").unwrap());
+}
+
+/// `None` should map to `nil`, and `Some` to the value itself.
+fn marshalls_options_as_nil_or_value(ruby: &mut Ruby) {
+    let mut long_name_player = Player { name: "Robert".to_owned(), ..Player::default() };
+    let mut short_name_player = Player::default();
+
+    ruby.plug("long_name_player", &mut long_name_player).unwrap();
+    ruby.plug("short_name_player", &mut short_name_player).unwrap();
+
+    assert_eq!(Value::string("Rob"), ruby.eval("LONG_NAME_PLAYER.nickname").unwrap());
+    assert_eq!(Value::nil(), ruby.eval("SHORT_NAME_PLAYER.nickname").unwrap());
+
+    ruby.eval("SHORT_NAME_PLAYER.move_by(nil)").unwrap();
+    ruby.eval("LONG_NAME_PLAYER.move_by(10)").unwrap();
+    assert_eq!(Player::default().x + 1, short_name_player.x);
+    assert_eq!(Player::default().x + 10, long_name_player.x);
+}
+
+/// Returning `Err` from Rust should raise an exception in Ruby.
+fn raises_exceptions_for_rust_errors(ruby: &mut Ruby) {
+    let mut player = Player::default();
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert_eq!(Value::integer(5), ruby.eval("PLAYER.teleport(5)").unwrap());
+    assert_eq!(Value::string("cannot teleport below zero"),
+               ruby.eval("begin; PLAYER.teleport(-1); rescue RuntimeError => e; e.message; end").unwrap());
+
+    // Errors of any type that can be displayed are supported.
+    assert_eq!(Value::integer(3), ruby.eval("PLAYER.climb(3)").unwrap());
+    assert_eq!(Value::string("cannot climb to 11"),
+               ruby.eval("begin; PLAYER.climb(11); rescue RuntimeError => e; e.message; end").unwrap());
+    assert_eq!(Value::string("Result<i32, Blocked>"),
+               ruby.eval("Player.rust_methods.find { |m| m[:name] == :climb }[:returns]").unwrap());
+}
+
+/// `Vec<T>`, `&[T]` and `[T; N]` should all map to Ruby arrays.
+fn marshalls_collections_as_arrays(ruby: &mut Ruby) {
+    let mut player = Player { name: "Bob the Builder".to_owned(), ..Player::default() };
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert_eq!(ruby.eval("['Bob', 'the', 'Builder']").unwrap(), ruby.eval("PLAYER.name_parts").unwrap());
+    assert_eq!(Value::string("a, b"), ruby.eval("PLAYER.join_names(['a', 'b'])").unwrap());
+    assert_eq!(Value::integer(6), ruby.eval("PLAYER.total([1, 2, 3])").unwrap());
+
+    ruby.eval("PLAYER.set_position([1, 2, 3])").unwrap();
+    assert_eq!(ruby.eval("[1, 2, 3]").unwrap(), ruby.eval("PLAYER.position").unwrap());
+    assert_eq!((1, 2, 3), (player.x, player.y, player.z));
+}
+
+/// If an element can't be marshalled, the error should say which one.
+fn reports_the_index_of_bad_elements(ruby: &mut Ruby) {
+    let mut player = Player::default();
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert_eq!(Value::string("element 1: expected String, got Integer"),
+               ruby.eval("begin; PLAYER.join_names(['a', 2]); rescue TypeError => e; e.message; end").unwrap());
+    assert_eq!(Value::string("expected 3 elements, got 2"),
+               ruby.eval("begin; PLAYER.set_position([1, 2]); rescue ArgumentError => e; e.message; end").unwrap());
+}
+
+/// `HashMap` and `BTreeMap` should map to Ruby hashes.
+fn marshalls_maps_as_hashes(ruby: &mut Ruby) {
+    let mut player = Player::default();
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert_eq!(ruby.eval("{ x: 453, y: -244, z: 0xbeef }").unwrap(),
+               ruby.eval("PLAYER.coordinates").unwrap());
+    assert_eq!(Value::integer(3), ruby.eval("PLAYER.sum_values('a' => 1, 'b' => 2)").unwrap());
+}
+
+/// Tuples should map to Ruby arrays.
+fn marshalls_tuples_as_arrays(ruby: &mut Ruby) {
+    let mut player = Player::default();
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert_eq!(ruby.eval("[453, 'Bob']").unwrap(), ruby.eval("PLAYER.bounds").unwrap());
+    assert_eq!(Value::string("level 3"), ruby.eval("PLAYER.describe(['level', 3])").unwrap());
+}
+
+/// Enums without fields should map to symbols.
+fn marshalls_fieldless_enums_as_symbols(ruby: &mut Ruby) {
+    let mut player = Player::default();
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert_eq!(Value::symbol("running"), ruby.eval("PLAYER.state").unwrap());
+    assert_eq!(Value::boolean(true), ruby.eval("PLAYER.is_in_state(:running)").unwrap());
+    assert_eq!(Value::boolean(false), ruby.eval("PLAYER.is_in_state(:idle)").unwrap());
+    assert!(ruby.eval("PLAYER.is_in_state(:flying)").is_err());
+}
+
+/// Enums with fields should map to objects with a `variant` and field accessors.
+fn marshalls_data_enums_as_objects(ruby: &mut Ruby) {
+    let mut player = Player::default();
+
+    ruby.plug("player", &mut player).unwrap();
+    let class = ruby.register_enum::<Command>().unwrap();
+    assert_eq!(class, ruby.register_enum::<Command>().unwrap());
+
+    assert_eq!(Value::symbol("move_to"), ruby.eval("PLAYER.perform(Command.new(:move_to, x: 4, y: 5)).variant").unwrap());
+    assert_eq!((4, 5), (player.x, player.y));
+
+    assert_eq!(Value::symbol("wait"), ruby.eval("PLAYER.perform(:wait).variant").unwrap());
+    assert_eq!(class, ruby.eval("PLAYER.perform(:wait).class").unwrap());
+
+    // Only the class that was defined for the enum is accepted.
+    ruby.eval("module Fake; class Command < PluggerEnum; end; end").unwrap();
+    assert!(ruby.eval("PLAYER.perform(Fake::Command.new(:wait))").is_err());
+
+    assert_eq!(Value::string("hello"), ruby.eval("PLAYER.perform(Command.new(:say, field0: 'hello')).field0").unwrap());
+}
+
+/// `&str`, `Cow<str>` and `char` should all map to Ruby strings.
+fn marshalls_strs_and_chars(ruby: &mut Ruby) {
+    let mut player = Player::default();
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert_eq!(Value::string("Hi, Bob"), ruby.eval("PLAYER.greet('Hi')").unwrap());
+    assert_eq!(Value::string("Bob"), ruby.eval("PLAYER.name_str").unwrap());
+    assert_eq!(Value::string("B"), ruby.eval("PLAYER.initial").unwrap());
+    assert_eq!(Value::boolean(true), ruby.eval("PLAYER.has_initial('B')").unwrap());
+    assert!(ruby.eval("PLAYER.has_initial('Bo')").is_err());
+    assert_eq!(Value::string("HEY"), ruby.eval("PLAYER.shout('hey')").unwrap());
+}
+
+/// `usize`, `isize`, `u128` and `i128` should map to Ruby integers, using
+/// bignums where necessary.
+fn marshalls_pointer_sized_and_128_bit_integers(ruby: &mut Ruby) {
+    let mut player = Player::default();
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert_eq!(Value::integer(-47), ruby.eval("PLAYER.distance(500)").unwrap());
+    assert_eq!(ruby.eval("(2**64 - 1) * 4").unwrap(), ruby.eval("PLAYER.huge").unwrap());
+    assert_eq!(ruby.eval("-(2**100)").unwrap(), ruby.eval("PLAYER.halve(-(2**101))").unwrap());
+    assert!(ruby.eval("PLAYER.halve(2**200)").is_err());
+}
+
+/// Integers that don't fit into a Ruby `Fixnum` should not be truncated.
+fn marshalls_64_bit_integers_losslessly(ruby: &mut Ruby) {
+    let mut player = Player::default();
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert_eq!(ruby.eval("2**64 - 2").unwrap(), ruby.eval("PLAYER.id").unwrap());
+    assert_eq!(Value::boolean(true), ruby.eval("PLAYER.is_id(2**64 - 2)").unwrap());
+}
+
+/// Integers that don't fit into the Rust type should raise a `RangeError`.
+fn raises_range_errors_for_integers_that_do_not_fit(ruby: &mut Ruby) {
+    let mut player = Player::default();
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert_eq!(Value::string("256 is out of range for u8"),
+               ruby.eval("begin; PLAYER.set_z(256); rescue RangeError => e; e.message; end").unwrap());
+    assert_eq!(Value::string("-1 is out of range for u64"),
+               ruby.eval("begin; PLAYER.is_id(-1); rescue RangeError => e; e.message; end").unwrap());
+    assert_eq!(Value::string("18446744073709551616 is out of range for u64"),
+               ruby.eval("begin; PLAYER.is_id(2**64); rescue RangeError => e; e.message; end").unwrap());
+}
+
+/// `Vec<u8>` and `&[u8]` should map to binary strings, not arrays.
+fn marshalls_bytes_as_binary_strings(ruby: &mut Ruby) {
+    let mut player = Player::default();
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert_eq!(ruby.eval("[0, 159, 146, 150, 255].pack('C*')").unwrap(), ruby.eval("PLAYER.save").unwrap());
+    assert_eq!(Value::string("ASCII-8BIT"), ruby.eval("PLAYER.save.encoding.to_s").unwrap());
+    // Bytes are not checked for valid UTF-8.
+    assert_eq!(Value::integer(710), ruby.eval("PLAYER.checksum([0, 159, 146, 150, 255].pack('C*'))").unwrap());
+    assert_eq!(Value::integer(710), ruby.eval("PLAYER.checksum(PLAYER.save)").unwrap());
+    assert_eq!(Value::string("expected String, got Array"),
+               ruby.eval("begin; PLAYER.checksum([1, 2]); rescue TypeError => e; e.message; end").unwrap());
+}
+
+/// Strings should be transcoded to UTF-8 on the way in, and always be
+/// tagged as UTF-8 on the way out.
+fn marshalls_strings_as_utf8(ruby: &mut Ruby) {
+    let mut player = Player { name: "Zoë".to_owned(), ..Player::default() };
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert_eq!(Value::string("UTF-8"), ruby.eval("PLAYER.name.encoding.to_s").unwrap());
+    assert_eq!(Value::boolean(true), ruby.eval("PLAYER.name == \"Zo\\u00EB\"").unwrap());
+    assert_eq!(Value::boolean(true),
+               ruby.eval("PLAYER.greet(\"Gr\\u00FC\\u00DF dich\".encode('ISO-8859-1')) == \"Gr\\u00FC\\u00DF dich, Zo\\u00EB\"").unwrap());
+    assert_eq!(Value::string("invalid byte sequence in UTF-8"),
+               ruby.eval("begin; PLAYER.greet(\"\\xFF\".force_encoding('UTF-8')); rescue ArgumentError => e; e.message; end").unwrap());
+    assert_eq!(Value::string("invalid byte sequence in ASCII-8BIT"),
+               ruby.eval("begin; PLAYER.greet(\"\\xFF\".b); rescue ArgumentError => e; e.message; end").unwrap());
+    assert_eq!(Value::string("invalid byte sequence in UTF-7"),
+               ruby.eval("begin; PLAYER.greet(\"+AGEA-\".force_encoding('UTF-7')); rescue ArgumentError => e; e.message; end").unwrap());
+}
+
+/// Symbols are only accepted for strings when the method asks for them.
+fn coerces_symbols_to_strings_when_asked(ruby: &mut Ruby) {
+    let mut player = Player::default();
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert_eq!(ruby.eval("PLAYER.salute('Hi')").unwrap(), ruby.eval("PLAYER.salute(:Hi)").unwrap());
+    assert_eq!(Value::string("expected String, got Symbol"),
+               ruby.eval("begin; PLAYER.greet(:Hi); rescue TypeError => e; e.message; end").unwrap());
+}
+
+/// By default, `bool` parameters accept any value, with only `nil` and `false`
+/// being false.
+fn follows_ruby_truthiness_for_bools(ruby: &mut Ruby) {
+    let mut player = Player::default();
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert_eq!(Value::boolean(false), ruby.eval("PLAYER.either(nil, false)").unwrap());
+    assert_eq!(Value::boolean(true), ruby.eval("PLAYER.either(nil, 0)").unwrap());
+    assert_eq!(Value::boolean(true), ruby.eval("PLAYER.either('', false)").unwrap());
+}
+
+/// `#[pluggable(strict_bool)]` only accepts `true` and `false`.
+fn rejects_non_booleans_when_strict(ruby: &mut Ruby) {
+    let mut player = Player::default();
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert_eq!(Value::boolean(true), ruby.eval("PLAYER.both(true, true)").unwrap());
+    assert_eq!(Value::boolean(false), ruby.eval("PLAYER.both(true, false)").unwrap());
+    assert_eq!(Value::string("expected true or false, got NilClass"),
+               ruby.eval("begin; PLAYER.both(true, nil); rescue TypeError => e; e.message; end").unwrap());
+    assert_eq!(Value::string("expected true or false, got Integer"),
+               ruby.eval("begin; PLAYER.both(1, true); rescue TypeError => e; e.message; end").unwrap());
+}
+
+/// `#[pluggable(serde)]` marshalls any `Serialize`/`Deserialize` type.
+fn marshalls_serde_types(ruby: &mut Ruby) {
+    let mut player = Player::default();
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert_eq!(ruby.eval("{ volume: 11, difficulty: { Custom: { damage: 1.5 } }, keys: ['w', 'a'] }").unwrap(),
+               ruby.eval("PLAYER.settings").unwrap());
+    assert_eq!(Value::integer(11), ruby.eval("PLAYER.volume_of(PLAYER.settings)").unwrap());
+    assert_eq!(Value::integer(3),
+               ruby.eval("PLAYER.volume_of(volume: 3, difficulty: :Easy, keys: [])").unwrap());
+    assert_eq!(Value::string("missing field `keys`"),
+               ruby.eval("begin; PLAYER.volume_of(volume: 3, difficulty: :Easy); rescue TypeError => e; e.message; end").unwrap());
+}
+
+/// Script results can be inspected without depending on `rurust::Value`.
+fn converts_values_to_language_neutral_values(ruby: &mut Ruby) {
+    use plugger_core::{Marshall, Value as Dynamic};
+
+    let mut player = Player::default();
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert_eq!(Dynamic::Array(vec![
+                   Dynamic::Int(1), Dynamic::from("two"), Dynamic::Symbol("three".to_owned()),
+                   Dynamic::Nil, Dynamic::Bool(true), Dynamic::Float(1.5),
+               ]),
+               ruby.eval_value("[1, 'two', :three, nil, true, 1.5]").unwrap());
+    assert_eq!(Dynamic::Map(vec![(Dynamic::from("big"), Dynamic::Int(1 << 100))]),
+               ruby.eval_value("{ 'big' => 2**100 }").unwrap());
+    assert_eq!(Dynamic::UInt(u128::max_value()), ruby.eval_value("2**128 - 1").unwrap());
+    assert_eq!(ruby.eval("2**128 - 1").unwrap(),
+               plugger_ruby::Marshall::from_dynamic(Dynamic::UInt(u128::max_value())).unwrap());
+    assert_eq!(Dynamic::Bytes(vec![0, 255]), ruby.eval_value("[0, 255].pack('C*')").unwrap());
+
+    let handle = ruby.eval_value("PLAYER").unwrap().as_object().unwrap();
+    assert_eq!(Some(&mut player as *mut Player), handle.downcast::<Player>());
+    assert_eq!(None, handle.downcast::<Enemy>());
+
+    // Neutral values can be given back to Ruby.
+    let value = plugger_ruby::Marshall::from_dynamic(Dynamic::Array(vec![
+        Dynamic::Object(handle), Dynamic::from("Bob"),
+    ])).unwrap();
+    assert_eq!(ruby.eval("PLAYER.name").unwrap(),
+               value.send("[]", &[Value::integer(0)]).call_no_args("name"));
+    assert_eq!(Value::string("Bob"), value.send("[]", &[Value::integer(1)]));
+}
+
+/// Methods can be called by name from Rust, without going through a VM.
+fn invokes_methods_by_name() {
+    use plugger_core::{InvokeError, MarshallError, Pluggable, Value as Dynamic};
+
+    let mut player = Player::default();
+    let class = Player::class();
+
+    assert_eq!(Ok(Dynamic::Int(453)), class.invoke(&mut player, "x", &[]));
+    assert_eq!(Ok(Dynamic::Nil), class.invoke(&mut player, "move_by", &[Dynamic::from(10)]));
+    assert_eq!(463, player.x);
+    assert_eq!(Ok(Dynamic::from("Hi, Bob")), class.invoke(&mut player, "greet", &[Dynamic::from("Hi")]));
+
+    assert_eq!(Err(InvokeError::UnknownMethod("fly".to_owned())), class.invoke(&mut player, "fly", &[]));
+    assert_eq!(Err(InvokeError::ArgumentCount { expected: 1, got: 0 }),
+               class.invoke(&mut player, "greet", &[]));
+    assert_eq!(Err(InvokeError::Marshall(MarshallError::TypeMismatch {
+                   expected: "String".to_owned(),
+                   got: "Int".to_owned(),
+               })),
+               class.invoke(&mut player, "greet", &[Dynamic::from(1)]));
+    assert_eq!(Err(InvokeError::WrongReceiver { expected: "Player".to_owned(), got: "Enemy" }),
+               class.invoke(&mut Enemy::default(), "x", &[]));
+
+    let enemy_class = Enemy::class();
+    assert_eq!(Ok(Dynamic::Int(100)), enemy_class.invoke_static("max_health", &[]));
+}
+
+/// Parameter and return types are described precisely, with pluggable types
+/// by their full path.
+fn describes_method_signatures() {
+    use plugger_core::{Pluggable, TypeDesc};
+
+    let class = Player::class();
+    let param_ty = |method: &str| class.method(method).unwrap().parameters[0].ty.clone();
+    let ret_ty = |method: &str| class.method(method).unwrap().ret.clone();
+
+    assert_eq!(TypeDesc::Reference {
+                   mutable: false,
+                   ty: Box::new(TypeDesc::Class { path: "plugger_test::Player", namespace: None }),
+               },
+               param_ty("longest_name"));
+    assert_eq!("&[i32]", param_ty("total").to_string());
+    assert_eq!("Vec<String>", param_ty("join_names").to_string());
+    assert_eq!("Option<i32>", param_ty("move_by").to_string());
+    assert_eq!("(String, u8)", param_ty("describe").to_string());
+    assert_eq!(Some(TypeDesc::Array { elem: Box::new(TypeDesc::Primitive("i32")), len: 3 }),
+               ret_ty("position"));
+    assert_eq!(Some(TypeDesc::Value("plugger_test::State")), ret_ty("state"));
+    assert_eq!(Some(TypeDesc::Named("Settings")), ret_ty("settings"));
+    assert_eq!(None, ret_ty("move_left"));
+}
+
+fn exports_classes_as_json_schema() {
+    use plugger_core::{schema, Pluggable};
+
+    plugger_core::register_class(Player::class());
+    plugger_core::register_class(Enemy::class());
+    // Registering a class again should replace it.
+    plugger_core::register_class(Enemy::class());
+
+    let json = schema::registered_to_json();
+
+    assert!(json.starts_with("{\n  \"version\": 1,\n"));
+    assert_eq!(1, json.matches("\"path\": \"plugger_test::Enemy\"").count());
+    assert!(json.find("plugger_test::Enemy").unwrap() < json.find("plugger_test::Player").unwrap());
+    assert!(json.contains(concat!(
+        "\"name\": \"max_health\",\n",
+        "          \"static\": true,\n",
+        "          \"parameters\": [],\n",
+        "          \"returns\": {\n",
+        "            \"kind\": \"primitive\",\n",
+        "            \"name\": \"u32\"\n",
+        "          }")));
+    assert!(json.contains("\"kind\": \"class\",\n"));
+    assert_eq!(json, schema::to_json(&plugger_core::registered_classes()));
+}
+
+/// Plugged classes should be described by RBS signatures and YARD stubs, with
+/// the Ruby types their values are marshalled as.
+fn generates_ruby_signatures() {
+    use plugger_core::Pluggable;
+    use plugger_ruby::signatures;
+
+    let rbs = signatures::rbs(&Player::class());
+    assert!(rbs.contains("class Player < PluggerObject\n"));
+    assert!(rbs.contains("  def longest_name: (Player other) -> String\n"));
+    assert!(rbs.contains("  def move_left: () -> void\n"));
+    assert!(rbs.contains("  def nickname: () -> String?\n"));
+    assert!(rbs.contains("  def sum_values: (Hash[String, Integer] values) -> Integer\n"));
+    assert!(rbs.contains("  def bounds: () -> [Integer, String]\n"));
+    assert!(rbs.contains("  def is_in_state: (Symbol | PluggerEnum state) -> bool\n"));
+    assert!(rbs.contains("  def either: (boolish a, boolish b) -> bool\n"));
+    assert!(rbs.contains("  def both: (bool a, bool b) -> bool\n"));
+    assert!(rbs.contains("  def save: () -> String\n"));
+    assert!(rbs.contains("  def teleport: (Integer x) -> Integer\n"));
+    assert!(rbs.contains("  def coordinates: () -> Hash[Symbol, Integer]\n"));
+    assert!(rbs.contains("  def salute: (String | Symbol greeting) -> String\n"));
+    assert!(signatures::rbs(&Enemy::class()).contains("  def self.max_health: () -> Integer\n"));
+    assert!(signatures::rbs(&Enemy::class()).contains("  MAX_HEALTH: Integer\n"));
+
+    let stub = signatures::yard_stub(&Player::class());
+    assert!(stub.contains(concat!(
+        "  # @param values [Hash{String => Integer}]\n",
+        "  # @return [Integer]\n",
+        "  def sum_values(values); end\n")));
+    assert!(stub.contains("  # @param dx [Integer, nil]\n"));
+    assert!(stub.contains("  # @return [Array(Integer, String)]\n"));
+
+    // Namespaced classes are declared and referred to by their full name.
+    let rbs = signatures::rbs(&game::Player::class());
+    assert!(rbs.contains("module Game\nend\n\nclass Game::Player < PluggerObject\n"));
+    assert!(rbs.contains("  def beats: (Game::Player other) -> bool\n"));
+    assert!(signatures::rbs(&game::Hash::class()).contains("class Items::Hash < PluggerObject\n"));
+    assert!(signatures::yard_stub(&game::Player::class()).contains("module Game\nend\n\n"));
+
+    // Files are named after the full name, and two classes can't share one.
+    let directory = std::env::temp_dir().join("plugger-test-signatures");
+    signatures::write(&[Player::class(), game::Player::class()], &directory).unwrap();
+    assert!(directory.join("sig/player.rbs").is_file());
+    assert!(directory.join("sig/game/player.rbs").is_file());
+    assert!(directory.join("stubs/game/player.rb").is_file());
+    assert!(signatures::write(&[Player::class(), Player::class()], &directory).is_err());
+}
+
+/// Doc comments should be kept, and be readable from Ruby.
+fn exposes_docs_through_help(ruby: &mut Ruby) {
+    use plugger_core::Pluggable;
+
+    let mut player = Player::default();
+    let class = Player::class();
+
+    assert_eq!("Someone playing the game.", class.docs);
+    assert_eq!("Moves the player one step to the left.\n\nPlayers can't move through walls.",
+               class.method("move_left").unwrap().docs);
+    assert_eq!("", class.method("move_by").unwrap().docs);
+
+    ruby.plug("player", &mut player).unwrap();
+
+    assert_eq!(Value::string("Someone playing the game."), ruby.eval("Player.help").unwrap());
+    assert_eq!(Value::string(class.method("move_left").unwrap().docs),
+               ruby.eval("Player.help(:move_left)").unwrap());
+    assert_eq!(ruby.eval("Player.help('move_left')").unwrap(), ruby.eval("PLAYER.help(:move_left)").unwrap());
+    assert_eq!(Value::string("no method named 'fly'"),
+               ruby.eval("begin; Player.help(:fly); rescue ArgumentError => e; e.message; end").unwrap());
+    assert!(plugger_ruby::signatures::yard_stub(&class).contains(concat!(
+        "  # Moves the player one step to the left.\n",
+        "  #\n",
+        "  # Players can't move through walls.\n",
+        "  #\n",
+        "  # @return [void]\n",
+        "  def move_left(); end\n")));
+}
+
+/// Plugged classes should describe their Rust methods to Ruby.
+fn reflects_over_rust_methods(ruby: &mut Ruby) {
+    let mut player = Player::default();
+    let mut enemy = Enemy::default();
+
+    ruby.plug("player", &mut player).unwrap();
+    ruby.plug("enemy", &mut enemy).unwrap();
+
+    let method = |name: &str| format!("Player.rust_methods.find {{ |m| m[:name] == :{} }}", name);
+
+    assert_eq!(Value::boolean(true), ruby.eval("PLAYER.rust_methods.equal?(Player.rust_methods)").unwrap());
+    assert_eq!(Value::nil(), ruby.eval(&format!("{}[:returns]", method("move_left"))).unwrap());
+    assert_eq!(Value::string("String"), ruby.eval(&format!("{}[:returns]", method("longest_name"))).unwrap());
+    assert_eq!(ruby.eval("[{ name: :dx, type: 'Option<i32>', ruby_type: 'Integer?' }]").unwrap(),
+               ruby.eval(&format!("{}[:parameters]", method("move_by"))).unwrap());
+    assert_eq!(Value::boolean(false), ruby.eval(&format!("{}[:static]", method("move_by"))).unwrap());
+    assert_eq!(Value::boolean(true),
+               ruby.eval("Enemy.rust_methods.find { |m| m[:name] == :max_health }[:static]").unwrap());
+    assert_eq!(Value::boolean(true), ruby.eval("Player.rust_methods.frozen?").unwrap());
+    assert_eq!(ruby.eval("[]").unwrap(), ruby.eval("Player.rust_fields").unwrap());
+    assert_eq!(ruby.eval("[{ name: :settings, type: 'Option<Settings>', ruby_type: 'untyped?', \
+                              docs: 'The settings the enemy was made with, if it was made by a player.' }]").unwrap(),
+               ruby.eval("Enemy.rust_fields").unwrap());
+}
+
+/// Rust methods should be real Ruby methods, rather than going through
+/// `method_missing`.
+fn defines_rust_methods_directly(ruby: &mut Ruby) {
+    let mut player = Player::default();
+    let mut enemy = Enemy::default();
+
+    ruby.plug("player", &mut player).unwrap();
+    ruby.plug("enemy", &mut enemy).unwrap();
+
+    assert_eq!(Value::boolean(true), ruby.eval("Player.instance_method(:move_by).owner == Player").unwrap());
+    assert_eq!(Value::integer(1), ruby.eval("Player.instance_method(:move_by).arity").unwrap());
+    assert_eq!(Value::boolean(true), ruby.eval("Enemy.singleton_class.method_defined?(:max_health)").unwrap());
+    assert_eq!(Value::boolean(false), ruby.eval("Player.method_defined?(:move_by_internal)").unwrap());
+    assert_eq!(Value::boolean(true), ruby.eval("Player.instance_method(:method_missing).owner == BasicObject").unwrap());
+    assert_eq!(Value::string("wrong number of arguments (given 2, expected 1)"),
+               ruby.eval("begin; PLAYER.move_by(1, 2); rescue ArgumentError => e; e.message; end").unwrap());
+    assert_eq!(Value::integer(100), ruby.eval("Enemy.method(:max_health).call").unwrap());
+
+    // Ruby subclasses inherit the plugged methods.
+    assert_eq!(Value::integer(100), ruby.eval("class Boss < Enemy; end; Boss.max_health").unwrap());
+
+    // The functions are kept out of reach of scripts.
+    assert!(ruby.eval("Player.instance_variable_defined?(:@rust_functions)").unwrap().is_false());
+}
+
+/// Classes can be registered without an instance, and are only defined once.
+fn registers_classes_once(ruby: &mut Ruby) {
+    let enemy_class = ruby.register_class::<Enemy>().unwrap();
+
+    assert_eq!(enemy_class, ruby.eval("Enemy").unwrap());
+    assert_eq!(Value::integer(100), ruby.eval("Enemy.max_health").unwrap());
+    assert_eq!(enemy_class, ruby.register_class::<Enemy>().unwrap());
+
+    let mut player = Player::default();
+    ruby.plug("player", &mut player).unwrap();
+    let rust_methods = ruby.eval("Player.rust_methods").unwrap();
+
+    let mut other_player = Player::default();
+    ruby.plug("other_player", &mut other_player).unwrap();
+
+    assert_eq!(Value::boolean(true),
+               rust_methods.send("equal?", &[ruby.eval("Player.rust_methods").unwrap()]));
+
+    // Names Ruby won't accept are rejected before the class is defined, so
+    // nothing is left half defined.
+    match ruby.register_class::<Misnamed>() {
+        Err(plugger_ruby::ErrorKind::InvalidName(ref name)) if name == "limit" => (),
+        _ => panic!("expected 'limit' to be rejected as a constant name"),
+    }
+    assert!(ruby.eval("Object.const_defined?(:Misnamed)").unwrap().is_false());
+    assert!(ruby.register_class::<Enemy>().is_ok());
+}
+
+/// Free functions should be plugged into Ruby modules named after their Rust
+/// module, and be callable by name from Rust.
+fn plugs_free_functions_into_modules(ruby: &mut Ruby) {
+    use plugger_core::{InvokeError, Value as Dynamic};
+
+    let world = world::pluggable_module();
+    assert_eq!("world", world.name);
+    assert_eq!(Ok(Dynamic::from("enemy at 1, 2")),
+               world.invoke("spawn_enemy", &[Dynamic::from(1), Dynamic::from(2)]));
+    assert_eq!(Err(InvokeError::UnknownMethod("secret".to_owned())), world.invoke("secret", &[]));
+
+    ruby.plug_module(&world).unwrap();
+    ruby.plug_module(&pluggable_version()).unwrap();
+
+    assert_eq!(ruby.eval("'enemy at 3, 4'").unwrap(), ruby.eval("World.spawn_enemy(3, 4)").unwrap());
+    assert_eq!(Value::float(9.8), ruby.eval("World.gravity").unwrap());
+    assert_eq!(ruby.eval("'1.0'").unwrap(), ruby.eval("PluggerTest.version").unwrap());
+
+    assert_eq!(ruby.eval("'Things that happen in the game world.'").unwrap(), ruby.eval("World.help").unwrap());
+    assert_eq!(ruby.eval("'Creates an enemy somewhere in the world.'").unwrap(),
+               ruby.eval("World.help(:spawn_enemy)").unwrap());
+    assert!(ruby.eval("World.respond_to?(:secret)").unwrap().is_false());
+
+    let toggle = pluggable_toggle();
+    assert_eq!(Some("Tools"), toggle.namespace);
+    assert!(toggle.functions[0].options.strict_bool);
+    ruby.plug_module(&toggle).unwrap();
+    assert_eq!(Value::boolean(false), ruby.eval("Tools::PluggerTest.toggle(true)").unwrap());
+}
+
+/// Associated constants and module constants should become frozen Ruby
+/// constants, with their docs.
+fn exposes_constants(ruby: &mut Ruby) {
+    use plugger_core::{Pluggable, Value as Dynamic};
+
+    let enemy_class = Enemy::class();
+    assert_eq!(Ok(Dynamic::Int(100)), enemy_class.constant("MAX_HEALTH").unwrap().value());
+    assert_eq!("The health of a new enemy.", enemy_class.constant("MAX_HEALTH").unwrap().docs);
+
+    ruby.register_class::<Enemy>().unwrap();
+    assert_eq!(Value::integer(100), ruby.eval("Enemy::MAX_HEALTH").unwrap());
+    assert_eq!(ruby.eval("'The health of a new enemy.'").unwrap(), ruby.eval("Enemy.help(:MAX_HEALTH)").unwrap());
+
+    assert_eq!(ruby.eval("'Earth'").unwrap(), ruby.eval("World::NAME").unwrap());
+    assert!(ruby.eval("World::NAME.frozen?").unwrap().is_true());
+
+    ruby.plug_module(&pluggable_max_players()).unwrap();
+    assert_eq!(Value::integer(4), ruby.eval("PluggerTest::MAX_PLAYERS").unwrap());
+}
+
+/// Classes should be nested in modules named after their Rust module path,
+/// or their given namespace, and never clash with existing constants.
+fn namespaces_classes_by_module_path(ruby: &mut Ruby) {
+    use plugger_core::Marshall;
+    use plugger_ruby::ErrorKind;
+
+    let mut game_player = game::Player::default();
+    ruby.plug("game_player", &mut game_player).unwrap();
+    assert_eq!(ruby.eval("Game::Player").unwrap(), ruby.eval("GAME_PLAYER.class").unwrap());
+    assert!(ruby.eval("Player != Game::Player").unwrap().is_true());
+
+    // Objects given back to Ruby are wrapped in their own class, not the
+    // class with the same short name.
+    let handle = ruby.eval_value("GAME_PLAYER").unwrap();
+    let wrapped = plugger_ruby::Marshall::from_dynamic(handle).unwrap();
+    assert_eq!(ruby.eval("Game::Player").unwrap(), wrapped.call_no_args("class"));
+    assert_eq!(Value::integer(0), wrapped.call_no_args("score"));
+
+    let order_class = ruby.register_enum::<game::Order>().unwrap();
+    assert_eq!(ruby.eval("Game::Orders::Order").unwrap(), order_class);
+
+    let hash_class = ruby.register_class::<game::Hash>().unwrap();
+    assert_eq!(ruby.eval("Items::Hash").unwrap(), hash_class);
+    assert!(ruby.eval("{}.is_a?(::Hash)").unwrap().is_true());
+
+    match ruby.register_class::<game::Enemy>() {
+        Err(ErrorKind::NameCollision { ref name, ref defined_by }) => {
+            assert_eq!("Enemy", name);
+            assert_eq!(&Some("plugger_test::Enemy".to_owned()), defined_by);
+        },
+        _ => panic!("expected a name collision"),
+    }
+
+    match ruby.plug_module(&math::pluggable_module()) {
+        Err(ErrorKind::NameCollision { ref name, ref defined_by }) => {
+            assert_eq!("Math", name);
+            assert_eq!(&None, defined_by);
+        },
+        _ => panic!("expected a name collision"),
+    }
+}
+
+/// Objects can be plugged in as constants, globals, script locals, or not
+/// bound to any name at all.
+fn binds_plugged_objects(ruby: &mut Ruby) {
+    use plugger_ruby::{Binding, ErrorKind};
+    use std::sync::{Arc, Mutex};
+
+    fn assert_send<T: Send>(_: &T) { }
+
+    let messages = Arc::new(Mutex::new(Vec::new()));
+    let logged = messages.clone();
+    ruby.set_logger(move |message| logged.lock().unwrap().push(message.to_owned()));
+    assert_send(ruby);
+
+    let mut player = Player::default();
+    let mut other_player = Player { x: 1, ..Player::default() };
+
+    ruby.eval("require 'stringio'; $stderr = StringIO.new").unwrap();
+    ruby.plug("hero", &mut player).unwrap();
+    ruby.plug("hero", &mut other_player).unwrap();
+    assert!(ruby.eval("$stderr.string.empty?").unwrap().is_true());
+    ruby.eval("$stderr = STDERR").unwrap();
+    assert_eq!(Value::integer(1), ruby.eval("HERO.x").unwrap());
+
+    ruby.plug_as(Binding::Global("hero"), &mut player).unwrap();
+    assert_eq!(Value::integer(453), ruby.eval("$hero.x").unwrap());
+
+    ruby.plug_as(Binding::Local("sidekick"), &mut other_player).unwrap();
+    assert_eq!(Value::integer(1), ruby.eval_script("sidekick.x").unwrap());
+    assert!(ruby.eval("defined?(sidekick)").unwrap().is_nil());
+
+    match ruby.plug_as(Binding::Local("end"), &mut player) {
+        Err(ErrorKind::InvalidName(ref name)) => assert_eq!("end", name),
+        _ => panic!("expected an invalid name"),
+    }
+    assert!(ruby.plug_as(Binding::Local("Sidekick"), &mut player).is_err());
+    assert!(ruby.plug_as(Binding::Local("side kick"), &mut player).is_err());
+
+    ruby.unbind(Binding::Local("sidekick")).unwrap();
+    assert!(ruby.eval_script("sidekick").is_err());
+    ruby.unbind(Binding::Global("hero")).unwrap();
+    assert!(ruby.eval("$hero").unwrap().is_nil());
+
+    let unbound = ruby.plug_as(Binding::Unbound, &mut player).unwrap();
+    assert_eq!(Value::integer(453), unbound.send("x", &[]));
+
+    assert!(ruby.plug("argv", &mut player).is_err());
+
+    assert_eq!(vec!["plugged in Player as HERO", "plugged in Player as HERO",
+                    "plugged in Player as $hero", "plugged in Player as sidekick",
+                    "plugged in Player"],
+               *messages.lock().unwrap());
+}